@@ -0,0 +1,51 @@
+//! Throughput of the parser on synthetic streams, run with `cargo bench`.
+use std::time::{Duration, Instant};
+
+use reqwest_sse::EventSource;
+use tokio_stream::StreamExt;
+
+mod support;
+
+use support::{SyntheticStream, response};
+
+/// Minimum duration of the measurements of a scenario.
+const DURATION: Duration = Duration::from_secs(1);
+
+async fn parse(body: &[u8], chunk_size: usize) -> usize {
+    let mut events = response(body, chunk_size).events().await.unwrap();
+    let mut count = 0;
+    while let Some(event) = events.next().await {
+        std::hint::black_box(event.unwrap());
+        count += 1;
+    }
+    count
+}
+
+#[allow(clippy::cast_precision_loss)]
+async fn bench(name: &str, stream: &SyntheticStream, chunk_size: usize) {
+    let body = stream.body();
+    let mut iterations = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < DURATION {
+        assert_eq!(parse(&body, chunk_size).await, stream.events());
+        iterations += 1;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let bytes = body.len() as f64 * f64::from(iterations);
+    let events = stream.events() as f64 * f64::from(iterations);
+    println!(
+        "{name:<24} {:>10.1} MB/s {:>12.0} events/s",
+        bytes / elapsed / 1e6,
+        events / elapsed,
+    );
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let small = SyntheticStream::new(100_000, 20).with_id();
+    bench("many small events", &small, 64 * 1024).await;
+    bench("fragmented small events", &small, 7).await;
+
+    let huge = SyntheticStream::new(10, 1024).data_lines(1024);
+    bench("few huge events", &huge, 64 * 1024).await;
+}
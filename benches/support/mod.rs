@@ -0,0 +1,74 @@
+//! Synthetic event streams, served as responses to parse.
+use std::convert::Infallible;
+
+use bytes::Bytes;
+use reqwest::{Body, Response};
+
+/// Generator of the body of a synthetic event stream.
+#[derive(Debug, Clone)]
+pub struct SyntheticStream {
+    events: usize,
+    data_lines: usize,
+    line_length: usize,
+    with_id: bool,
+}
+
+impl SyntheticStream {
+    /// Stream of `events` events made of a single `data` line of `line_length` bytes.
+    pub fn new(events: usize, line_length: usize) -> Self {
+        Self {
+            events,
+            data_lines: 1,
+            line_length,
+            with_id: false,
+        }
+    }
+
+    /// Splits the data of each event into `data_lines` lines.
+    pub fn data_lines(mut self, data_lines: usize) -> Self {
+        self.data_lines = data_lines;
+        self
+    }
+
+    /// Gives each event an `id` field.
+    pub fn with_id(mut self) -> Self {
+        self.with_id = true;
+        self
+    }
+
+    /// Returns the number of events of the stream.
+    pub fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Generates the body of the stream.
+    pub fn body(&self) -> Vec<u8> {
+        let line = "x".repeat(self.line_length);
+        let mut body = Vec::new();
+        for index in 0..self.events {
+            if self.with_id {
+                body.extend_from_slice(format!("id: {index}\n").as_bytes());
+            }
+            for _ in 0..self.data_lines {
+                body.extend_from_slice(b"data: ");
+                body.extend_from_slice(line.as_bytes());
+                body.push(b'\n');
+            }
+            body.push(b'\n');
+        }
+        body
+    }
+}
+
+/// Wraps the body into an event stream response, delivered in chunks of `chunk_size` bytes.
+pub fn response(body: &[u8], chunk_size: usize) -> Response {
+    let chunks = body
+        .chunks(chunk_size)
+        .map(|chunk| Ok::<_, Infallible>(Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    http::Response::builder()
+        .header("content-type", "text/event-stream")
+        .body(Body::wrap_stream(tokio_stream::iter(chunks)))
+        .unwrap()
+        .into()
+}
@@ -0,0 +1,122 @@
+//! Derive macro of the `reqwest_sse::json::SseEvent` trait.
+//!
+//! Use it through the `derive` feature of `reqwest-sse`, which re-exports it.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Variant, parse_macro_input};
+
+/// Maps the event types to the variants of an enum.
+///
+/// Each variant matches the events whose type is its name, or the one given with
+/// `#[sse(rename = "...")]`. A unit variant ignores the data of the event, while the data is
+/// deserialized from JSON into the field of a newtype variant. A single unit variant can be
+/// marked with `#[sse(other)]` to match the remaining event types.
+#[proc_macro_derive(SseEvent, attributes(sse))]
+pub fn derive_sse_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct VariantAttributes {
+    rename: Option<LitStr>,
+    other: bool,
+}
+
+fn parse_attributes(variant: &Variant) -> syn::Result<VariantAttributes> {
+    let mut attributes = VariantAttributes {
+        rename: None,
+        other: false,
+    };
+    for attribute in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("sse"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                attributes.rename = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("other") {
+                attributes.other = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename` or `other`"))
+            }
+        })?;
+    }
+    Ok(attributes)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "SseEvent can only be derived for enums",
+        ));
+    };
+
+    let mut arms = Vec::new();
+    let mut fallback = None;
+    for variant in &data.variants {
+        let attributes = parse_attributes(variant)?;
+        let ident = &variant.ident;
+        if attributes.other {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new_spanned(
+                    variant,
+                    "`#[sse(other)]` variant must be a unit variant",
+                ));
+            }
+            if fallback.is_some() {
+                return Err(Error::new_spanned(
+                    variant,
+                    "only one variant can be marked with `#[sse(other)]`",
+                ));
+            }
+            fallback = Some(quote! { _ => ::std::result::Result::Ok(Self::#ident) });
+            continue;
+        }
+
+        let event_type = attributes
+            .rename
+            .unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+        let value = match &variant.fields {
+            Fields::Unit => quote! { Self::#ident },
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote! {
+                Self::#ident(event.json().map_err(::reqwest_sse::error::EventError::JsonError)?)
+            },
+            _ => {
+                return Err(Error::new_spanned(
+                    variant,
+                    "SseEvent variants must be unit or newtype variants",
+                ));
+            }
+        };
+        arms.push(quote! { #event_type => ::std::result::Result::Ok(#value) });
+    }
+    let fallback = fallback.unwrap_or_else(|| {
+        quote! {
+            _ => ::std::result::Result::Err(
+                ::reqwest_sse::error::EventError::UnknownEventType(event.event_type.to_string()),
+            )
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::reqwest_sse::json::SseEvent for #name #type_generics #where_clause {
+            fn from_event(
+                event: &::reqwest_sse::Event,
+            ) -> ::std::result::Result<Self, ::reqwest_sse::error::EventError> {
+                match &*event.event_type {
+                    #(#arms,)*
+                    #fallback,
+                }
+            }
+        }
+    })
+}
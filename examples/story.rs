@@ -14,7 +14,7 @@ async fn main() {
         .unwrap();
 
     while let Some(Ok(event)) = events.next().await {
-        if event.event_type == "message" {
+        if &*event.event_type == "message" {
             print!("{} ", event.data);
             std::io::stdout().flush().unwrap();
         }
@@ -0,0 +1,158 @@
+//! Combination of a snapshot, fetched by other means, with a live stream of events.
+//!
+//! A common pattern is to fetch the current state through a REST endpoint, then apply the changes
+//! streamed as events. [`backfill`] runs the snapshot request while consuming the live stream,
+//! buffering the events received meanwhile, so none is missed between the two.
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::{
+//!     EventSource,
+//!     backfill::{Backfilled, backfill},
+//! };
+//!
+//! # async fn run() {
+//! // connect to the live stream first, so no event is missed
+//! let live = reqwest::get("https://example.com/events")
+//!     .await.unwrap()
+//!     .events()
+//!     .await.unwrap();
+//! let snapshot = async {
+//!     let body = reqwest::get("https://example.com/items").await?.text().await?;
+//!     Ok::<_, reqwest::Error>(body.lines().map(String::from).collect::<Vec<_>>())
+//! };
+//!
+//! let mut items = backfill(live, async { snapshot.await.unwrap() });
+//! while let Some(item) = items.next().await {
+//!     match item {
+//!         Backfilled::Snapshot(item) => println!("initial item: {item}"),
+//!         Backfilled::Live(event) => println!("update: {event:?}"),
+//!     }
+//! }
+//! # }
+//! ```
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+/// Item of a [`backfill`] stream.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Backfilled<T, L> {
+    /// An item produced by the snapshot.
+    Snapshot(T),
+    /// An item of the live stream.
+    Live(L),
+}
+
+/// Runs the `snapshot` future while buffering the items of the `live` stream, then yields the
+/// snapshot items followed by the buffered and upcoming live items, in order.
+///
+/// The live stream should be connected before calling this function, so it covers everything
+/// happening after the snapshot. The buffer isn't bounded: it holds all the live items received
+/// while the snapshot is fetched.
+pub fn backfill<L, F, I>(live: L, snapshot: F) -> Backfill<L, F, I>
+where
+    L: Stream + Unpin,
+    F: Future<Output = I>,
+    I: IntoIterator,
+{
+    Backfill {
+        live,
+        snapshot: Some(Box::pin(snapshot)),
+        items: None,
+        buffer: VecDeque::new(),
+        live_ended: false,
+    }
+}
+
+/// Stream returned by [`backfill`].
+pub struct Backfill<L: Stream, F, I: IntoIterator> {
+    live: L,
+    /// The snapshot future, until it completes.
+    snapshot: Option<Pin<Box<F>>>,
+    /// The snapshot items left to yield.
+    items: Option<I::IntoIter>,
+    /// The live items received while the snapshot is fetched.
+    buffer: VecDeque<L::Item>,
+    live_ended: bool,
+}
+
+// none of the fields is pinned: the snapshot future is boxed
+impl<L: Stream, F, I: IntoIterator> Unpin for Backfill<L, F, I> {}
+
+impl<L, F, I> Stream for Backfill<L, F, I>
+where
+    L: Stream + Unpin,
+    F: Future<Output = I>,
+    I: IntoIterator,
+{
+    type Item = Backfilled<I::Item, L::Item>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while let Some(snapshot) = &mut this.snapshot {
+            if let Poll::Ready(items) = snapshot.as_mut().poll(cx) {
+                this.snapshot = None;
+                this.items = Some(items.into_iter());
+                break;
+            }
+            if this.live_ended {
+                return Poll::Pending;
+            }
+            match Pin::new(&mut this.live).poll_next(cx) {
+                Poll::Ready(Some(item)) => this.buffer.push_back(item),
+                Poll::Ready(None) => this.live_ended = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        if let Some(item) = this.items.as_mut().and_then(Iterator::next) {
+            return Poll::Ready(Some(Backfilled::Snapshot(item)));
+        }
+        this.items = None;
+        if let Some(item) = this.buffer.pop_front() {
+            return Poll::Ready(Some(Backfilled::Live(item)));
+        }
+        if this.live_ended {
+            return Poll::Ready(None);
+        }
+        Pin::new(&mut this.live)
+            .poll_next(cx)
+            .map(|item| item.map(Backfilled::Live))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn yield_snapshot_before_buffered_live_items() {
+        let live = tokio_stream::iter([1, 2, 3]);
+        let snapshot = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            ["a", "b"]
+        };
+
+        let items = backfill(live, snapshot).collect::<Vec<_>>().await;
+        assert_eq!(
+            items,
+            [
+                Backfilled::Snapshot("a"),
+                Backfilled::Snapshot("b"),
+                Backfilled::Live(1),
+                Backfilled::Live(2),
+                Backfilled::Live(3),
+            ]
+        );
+    }
+}
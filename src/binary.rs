@@ -0,0 +1,115 @@
+//! Decoding of binary payloads, base64-encoded into the data of events.
+//!
+//! Enabled by the `base64` feature. A [`BinaryEvents`] stream decodes the data of each event of
+//! the stream it wraps with [`Event::decode_base64`], then into a typed message with a decode
+//! function, e.g. `prost::Message::decode` for protobuf messages.
+//!
+//! ```rust,ignore
+//! use prost::Message;
+//!
+//! let mut messages = response
+//!     .events()
+//!     .await?
+//!     .decode_binary(|bytes| MyMessage::decode(bytes));
+//! ```
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use bytes::Bytes;
+use futures_core::Stream;
+
+use crate::{Event, error::EventError, stream::EventStream};
+
+/// Stream of the messages decoded from the binary payloads of the events of the wrapped stream.
+///
+/// Errors of the wrapped stream are forwarded as is, while the payloads that can't be decoded are
+/// yielded as [`EventError::Base64Error`] or [`EventError::DecodeError`], and the stream goes on.
+pub struct BinaryEvents<S, F, T> {
+    stream: S,
+    decode: F,
+    message: PhantomData<fn() -> T>,
+}
+
+impl<S, F, T> BinaryEvents<S, F, T> {
+    /// Wraps the stream of events, decoding their payloads with the function.
+    #[must_use]
+    pub fn new(stream: S, decode: F) -> Self {
+        Self {
+            stream,
+            decode,
+            message: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: std::fmt::Debug, F, T> std::fmt::Debug for BinaryEvents<S, F, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BinaryEvents")
+            .field("stream", &self.stream)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, F, T, E> Stream for BinaryEvents<S, F, T>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+    F: FnMut(Bytes) -> Result<T, E> + Unpin,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Item = Result<T, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let item = ready!(Pin::new(&mut this.stream).poll_next(cx));
+        Poll::Ready(item.map(|event| {
+            let bytes = event?.decode_base64()?;
+            (this.decode)(bytes).map_err(|error| EventError::DecodeError(Box::new(error)))
+        }))
+    }
+}
+
+impl EventStream {
+    /// Decodes the base64 payload of each event with the function, see [`BinaryEvents`].
+    #[must_use]
+    pub fn decode_binary<F, T, E>(self, decode: F) -> BinaryEvents<Self, F, T>
+    where
+        F: FnMut(Bytes) -> Result<T, E>,
+    {
+        BinaryEvents::new(self, decode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::event;
+
+    #[tokio::test]
+    async fn decode_binary_payloads() {
+        let events = tokio_stream::iter(vec![
+            Ok(event("aGk=")),
+            Ok(event("not base64")),
+            Ok(event("/w==")),
+        ]);
+        let messages = BinaryEvents::new(events, |bytes: Bytes| String::from_utf8(bytes.to_vec()))
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].as_ref().unwrap(), "hi");
+        assert!(matches!(messages[1], Err(EventError::Base64Error(_))));
+        assert!(
+            matches!(&messages[2], Err(EventError::DecodeError(error)) if error.is::<std::string::FromUtf8Error>())
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Blocking consumption of event streams, with [`reqwest::blocking`].
+//!
+//! Enabled by the `blocking` feature. The body of a blocking [`Response`] is read by the same
+//! parser as the async one, without an async runtime, as an [`Iterator`] of [`Event`]s.
+//!
+//! ```rust,no_run
+//! use reqwest_sse::blocking::EventSource;
+//!
+//! let events = reqwest::blocking::get("https://sse.test-free.online/api/story")
+//!     .unwrap()
+//!     .events()
+//!     .unwrap();
+//!
+//! for event in events {
+//!     println!("{event:?}");
+//! }
+//! ```
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use reqwest::blocking::Response;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+use crate::{
+    Event, Mode, Parsed, Parser, check_response,
+    config::EventSourceConfig,
+    error::{EventError, EventSourceError},
+    stats::{EventTypeStats, Stats, StreamStats},
+    stream::SharedState,
+};
+
+/// A trait for consuming a blocking [`Response`] as an [`Iterator`] of Server-Sent [`Event`]s.
+pub trait EventSource {
+    /// Converts the [`Response`] into an iterator of Server-Sent Events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EventSourceError`] if the response status isn't `200`, or its content type
+    /// isn't `text/event-stream`. A `204 No Content` has no events.
+    fn events(self) -> Result<Events, EventSourceError>;
+
+    /// Converts the [`Response`] into an iterator of Server-Sent Events, like
+    /// [`EventSource::events`], with the given [`EventSourceConfig`].
+    ///
+    /// The response is checked as an async one. Its validator sees the status, version and headers
+    /// of the response, but not its URL. Timeouts aren't applied: set the timeout of the blocking
+    /// client ([`reqwest::blocking::ClientBuilder::timeout`]) to bound the reads instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EventSourceError`] if the response status or content type isn't accepted, or
+    /// the validator rejects it.
+    fn events_with(self, config: EventSourceConfig) -> Result<Events, EventSourceError>;
+}
+
+impl EventSource for Response {
+    fn events(self) -> Result<Events, EventSourceError> {
+        self.events_with(EventSourceConfig::default())
+    }
+
+    fn events_with(self, config: EventSourceConfig) -> Result<Events, EventSourceError> {
+        let status = self.status();
+        if let Err(error) = check_response(status, self.headers(), &config, |validator| {
+            validator.validate(&head(&self))
+        }) {
+            return Err(match (error, config.error_body) {
+                (EventSourceError::BadStatus(status, None), Some(max_size)) => {
+                    let mut body = Vec::new();
+                    let _ = self.take(max_size as u64).read_to_end(&mut body);
+                    EventSourceError::BadStatus(
+                        status,
+                        Some(String::from_utf8_lossy(&body).into_owned()),
+                    )
+                }
+                (error, _) => error,
+            });
+        }
+
+        // the timeouts need a Tokio runtime, and can't interrupt a blocking read anyway
+        let config = EventSourceConfig {
+            idle_timeout: None,
+            first_event_timeout: None,
+            heartbeat: None,
+            max_duration: None,
+            ..config
+        };
+        let state = SharedState::default();
+        state.stats().record_connection();
+        let reader = BlockingReader(BufReader::new(self), Arc::clone(state.stats()));
+        Ok(Events {
+            parser: Parser::new(Box::new(reader), state.clone(), config, Mode::Events).blocking(),
+            state,
+        })
+    }
+}
+
+/// Copies the head of a blocking [`Response`] into an async one without body, for the validator.
+fn head(response: &Response) -> reqwest::Response {
+    let mut head = http::Response::new(Vec::new());
+    *head.status_mut() = response.status();
+    *head.version_mut() = response.version();
+    head.headers_mut().clone_from(response.headers());
+    reqwest::Response::from(head)
+}
+
+/// Iterator of the events of a blocking [`Response`], returned by [`EventSource::events`].
+///
+/// Each call to [`Iterator::next`] blocks until the next event is read.
+pub struct Events {
+    parser: Parser,
+    state: SharedState,
+}
+
+impl Events {
+    /// Returns the last event ID of the stream, like
+    /// [`EventStream::last_event_id`](crate::stream::EventStream::last_event_id).
+    #[must_use]
+    pub fn last_event_id(&self) -> Option<String> {
+        self.state.lock().last_event_id.clone()
+    }
+
+    /// Returns the last reconnection time sent by the server, like
+    /// [`EventStream::retry`](crate::stream::EventStream::retry).
+    #[must_use]
+    pub fn retry(&self) -> Option<Duration> {
+        self.state.lock().retry
+    }
+
+    /// Returns a snapshot of the statistics of the stream, like
+    /// [`EventStream::stats`](crate::stream::EventStream::stats).
+    #[must_use]
+    pub fn stats(&self) -> StreamStats {
+        self.state.stats().snapshot()
+    }
+
+    /// Returns the statistics of the events by type, like
+    /// [`EventStream::event_type_stats`](crate::stream::EventStream::event_type_stats).
+    #[must_use]
+    pub fn event_type_stats(&self) -> HashMap<String, EventTypeStats> {
+        self.state.stats().event_type_stats()
+    }
+}
+
+impl std::fmt::Debug for Events {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Events")
+            .field("state", &*self.state.lock())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Iterator for Events {
+    type Item = Result<Event, EventError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // reads block instead of being pending, so no waker is needed
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            match Pin::new(&mut self.parser).poll_next(&mut cx) {
+                Poll::Ready(Some(Ok(Parsed::Event(event, _)))) => return Some(Ok(event)),
+                Poll::Ready(Some(Ok(_))) => {}
+                Poll::Ready(Some(Err(error))) => return Some(Err(error)),
+                Poll::Ready(None) => return None,
+                Poll::Pending => unreachable!("blocking reads are never pending"),
+            }
+        }
+    }
+}
+
+/// Adapter of a blocking reader to [`AsyncBufRead`], whose reads block instead of being pending.
+struct BlockingReader<R>(R, Arc<Stats>);
+
+impl<R: BufRead + Unpin> AsyncRead for BlockingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let read = this.0.read(buf.initialize_unfilled())?;
+        this.1.record_bytes(read);
+        buf.advance(read);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: BufRead + Unpin> AsyncBufRead for BlockingReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Poll::Ready(self.get_mut().0.fill_buf())
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        let this = self.get_mut();
+        this.0.consume(amount);
+        this.1.record_bytes(amount);
+    }
+}
@@ -0,0 +1,154 @@
+//! Circuit breaker freezing reconnections to an event source that keeps failing.
+//!
+//! A [`CircuitBreaker`] counts consecutive connection failures. Once the failure threshold is
+//! reached, the circuit opens and connection attempts are rejected for the open duration. Then a
+//! single probe attempt is allowed: its success closes the circuit, its failure opens it again.
+//! A connection fails if its request does, or if its response is not an accepted event stream.
+//!
+//! Clones share the same state, so a single breaker can guard all the subscriptions to an
+//! endpoint, stopping them from reconnecting in lockstep while it is down.
+use std::{
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// State of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CircuitState {
+    /// Connection attempts are allowed.
+    Closed,
+    /// Connection attempts are rejected until the open duration elapses.
+    Open,
+    /// A single probe attempt is in flight, other attempts are rejected.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    failures: u32,
+    since: Instant,
+}
+
+/// Circuit breaker shared by reconnecting event sources.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a new closed [`CircuitBreaker`], opening after `failure_threshold` consecutive
+    /// failures for `open_duration`.
+    #[must_use]
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold,
+            open_duration,
+            inner: Arc::new(Mutex::new(Inner {
+                state: CircuitState::Closed,
+                failures: 0,
+                since: Instant::now(),
+            })),
+        }
+    }
+
+    /// Returns the current [`CircuitState`].
+    #[must_use]
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Asks permission for a connection attempt.
+    ///
+    /// On rejection, returns the state of the circuit and how long to wait before asking again.
+    pub(crate) fn try_acquire(&self) -> Result<(), (CircuitState, Duration)> {
+        let mut inner = self.lock();
+        let elapsed = inner.since.elapsed();
+        match inner.state {
+            CircuitState::Closed => Ok(()),
+            // A probe lasting longer than the open duration is considered lost, e.g. its stream
+            // was dropped before completion, so another one is allowed.
+            CircuitState::Open | CircuitState::HalfOpen if elapsed >= self.open_duration => {
+                inner.state = CircuitState::HalfOpen;
+                inner.since = Instant::now();
+                Ok(())
+            }
+            state => Err((state, self.open_duration.saturating_sub(elapsed))),
+        }
+    }
+
+    /// Records a successful connection, closing the circuit.
+    pub(crate) fn record_success(&self) {
+        let mut inner = self.lock();
+        inner.state = CircuitState::Closed;
+        inner.failures = 0;
+    }
+
+    /// Records a failed connection, opening the circuit if needed.
+    pub(crate) fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.failures = inner.failures.saturating_add(1);
+        if inner.state == CircuitState::HalfOpen || inner.failures >= self.failure_threshold {
+            inner.state = CircuitState::Open;
+            inner.since = Instant::now();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_after_threshold() {
+        let breaker = CircuitBreaker::new(2, Duration::from_mins(1));
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire().is_ok());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(
+            breaker.try_acquire(),
+            Err((CircuitState::Open, _))
+        ));
+    }
+
+    #[test]
+    fn probe_when_open_duration_elapsed() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert!(breaker.try_acquire().is_ok());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert!(breaker.try_acquire().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn single_probe_while_half_open() {
+        let breaker = CircuitBreaker::new(1, Duration::from_mins(1));
+        {
+            let mut inner = breaker.lock();
+            inner.state = CircuitState::HalfOpen;
+            inner.since = Instant::now();
+        }
+        assert!(matches!(
+            breaker.try_acquire(),
+            Err((CircuitState::HalfOpen, _))
+        ));
+    }
+}
@@ -0,0 +1,621 @@
+//! Configuration of event streams.
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use reqwest::{Response, StatusCode};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::ParseWarning;
+
+/// Configuration of the conversion of a [`reqwest::Response`] into a stream of
+/// [`Event`](crate::Event)s.
+///
+/// It gathers all the options, set with its builder methods, so they can be combined:
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use reqwest_sse::config::{EventSourceConfig, ParseMode};
+///
+/// let config = EventSourceConfig::new()
+///     .parse_mode(ParseMode::Lenient)
+///     .stale_timeout(Duration::from_secs(30))
+///     .max_line_length(64 * 1024);
+/// ```
+///
+/// Used by [`EventSource::events_with`](crate::EventSource::events_with) and by
+/// [`ReconnectingEventSource::config`](crate::reconnect::ReconnectingEventSource::config) for
+/// each connection.
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct EventSourceConfig {
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) stale_timeout: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) idle_activity: IdleActivity,
+    pub(crate) first_event_timeout: Option<Duration>,
+    pub(crate) heartbeat: Option<Duration>,
+    pub(crate) max_duration: Option<Duration>,
+    pub(crate) parse_mode: ParseMode,
+    pub(crate) truncated_event: Option<TruncatedEventPolicy>,
+    pub(crate) invalid_utf8: InvalidUtf8Policy,
+    pub(crate) max_line_length: Option<usize>,
+    pub(crate) long_line: LongLinePolicy,
+    pub(crate) max_event_size: Option<usize>,
+    pub(crate) read_buffer_capacity: Option<usize>,
+    pub(crate) unknown_fields: bool,
+    pub(crate) event_types: Option<Vec<String>>,
+    pub(crate) aliases: Vec<(String, Field)>,
+    pub(crate) raw: bool,
+    pub(crate) received_at: bool,
+    pub(crate) sequence: bool,
+    pub(crate) on_warning: Option<WarningHandler>,
+    pub(crate) accept_status: Option<StatusPredicate>,
+    pub(crate) error_body: Option<usize>,
+    pub(crate) accept_content_type: Option<ContentTypePredicate>,
+    pub(crate) any_content_type: bool,
+    pub(crate) validator: Option<Validator>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
+}
+
+/// Callback receiving the [`ParseWarning`]s.
+#[derive(Clone)]
+pub(crate) struct WarningHandler(Arc<dyn Fn(ParseWarning) + Send + Sync>);
+
+impl WarningHandler {
+    pub(crate) fn call(&self, warning: ParseWarning) {
+        (self.0)(warning);
+    }
+}
+
+impl Debug for WarningHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WarningHandler")
+    }
+}
+
+/// Predicate telling if a response status is accepted.
+#[derive(Clone)]
+pub(crate) struct StatusPredicate(Arc<dyn Fn(StatusCode) -> bool + Send + Sync>);
+
+impl Debug for StatusPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StatusPredicate")
+    }
+}
+
+/// Predicate telling if a response MIME type is accepted.
+#[derive(Clone)]
+pub(crate) struct ContentTypePredicate(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl Debug for ContentTypePredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ContentTypePredicate")
+    }
+}
+
+/// Function checking a response, returning an error message if rejected.
+type ValidatorFn = dyn Fn(&Response) -> Result<(), String> + Send + Sync;
+
+/// Custom check of the response before streaming its events.
+#[derive(Clone)]
+pub(crate) struct Validator(Arc<ValidatorFn>);
+
+impl Validator {
+    pub(crate) fn validate(&self, response: &Response) -> Result<(), String> {
+        (self.0)(response)
+    }
+}
+
+impl Debug for Validator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Validator")
+    }
+}
+
+/// How strictly the event stream format is enforced.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ParseMode {
+    /// Follows the [WHATWG algorithm](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation)
+    /// exactly:
+    /// - field names are case-sensitive,
+    /// - `retry` values must only contain ASCII digits,
+    /// - an event not terminated by a blank line at the end of the stream is discarded, unless
+    ///   set otherwise with [`EventSourceConfig::truncated_event`].
+    #[default]
+    Strict,
+    /// Forgives sloppy servers:
+    /// - field names are case-insensitive,
+    /// - `retry` values are trimmed and their leading digits used, e.g. `5000ms` is `5000`,
+    /// - an event not terminated by a blank line at the end of the stream is dispatched, unless
+    ///   set otherwise with [`EventSourceConfig::truncated_event`].
+    Lenient,
+}
+
+/// What to do with an event not terminated by a blank line at the end of the stream.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TruncatedEventPolicy {
+    /// The event is silently discarded, as per the specification.
+    Discard,
+    /// The event is dispatched as if it was complete.
+    Dispatch,
+    /// The stream yields an
+    /// [`EventError::TruncatedEvent`](crate::error::EventError::TruncatedEvent) with the event.
+    Error,
+}
+
+/// What to do with a line that isn't valid UTF-8.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum InvalidUtf8Policy {
+    /// The stream yields an [`EventError::Utf8Error`](crate::error::EventError::Utf8Error) and
+    /// ends.
+    #[default]
+    Strict,
+    /// Invalid sequences are replaced by `U+FFFD REPLACEMENT CHARACTER`.
+    Lossy,
+    /// The line is ignored, and the event it belongs to is discarded once complete. Its other
+    /// fields, such as `id` or `retry`, still apply.
+    SkipEvent,
+}
+
+/// What to do with a line longer than [`EventSourceConfig::max_line_length`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum LongLinePolicy {
+    /// The stream yields an [`EventError::LineTooLong`](crate::error::EventError::LineTooLong),
+    /// ignores the line, and continues.
+    Skip,
+    /// The stream yields an [`EventError::LineTooLong`](crate::error::EventError::LineTooLong) and
+    /// ends.
+    #[default]
+    Terminate,
+}
+
+/// What resets the timer of [`EventSourceConfig::idle_timeout`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum IdleActivity {
+    /// A line is read, comments included, so the keep-alive comments sent by servers, e.g.
+    /// `: ping`, count as activity.
+    #[default]
+    Lines,
+    /// An event is dispatched, so the streams sending only comments are considered idle.
+    Events,
+    /// Bytes are read from the body, comments and partial lines included.
+    Bytes,
+}
+
+/// Fields known by the parser.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Field {
+    /// `event` field, setting the event type.
+    Event,
+    /// `data` field, extending the event data.
+    Data,
+    /// `id` field, setting the last event ID.
+    Id,
+    /// `retry` field, setting the reconnection time.
+    Retry,
+}
+
+impl Field {
+    /// Recognizes the field name, among the aliases first, ignoring its case in
+    /// [`ParseMode::Lenient`].
+    pub(crate) fn parse(name: &str, config: &EventSourceConfig) -> Option<Self> {
+        let matches = |expected: &str| match config.parse_mode {
+            ParseMode::Strict => name == expected,
+            ParseMode::Lenient => name.eq_ignore_ascii_case(expected),
+        };
+        if let Some((_, field)) = config.aliases.iter().find(|(alias, _)| matches(alias)) {
+            Some(*field)
+        } else if matches("event") {
+            Some(Self::Event)
+        } else if matches("data") {
+            Some(Self::Data)
+        } else if matches("id") {
+            Some(Self::Id)
+        } else if matches("retry") {
+            Some(Self::Retry)
+        } else {
+            None
+        }
+    }
+}
+
+impl EventSourceConfig {
+    /// Creates a default [`EventSourceConfig`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum duration to receive and validate the response, when the request is sent by
+    /// this crate, e.g. with [`EventSourceRequest`](crate::EventSourceRequest) or
+    /// [`ReconnectingEventSource`](crate::reconnect::ReconnectingEventSource).
+    ///
+    /// Unlike the client's timeout, it doesn't bound the reading of the body: use
+    /// [`EventSourceConfig::stale_timeout`] for that.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum duration without receiving any byte, comments included, i.e. the idle
+    /// timeout between reads of the body.
+    ///
+    /// Once elapsed, the connection is considered stale: the stream yields an
+    /// [`EventError::StaleConnection`](crate::error::EventError::StaleConnection) and ends.
+    #[must_use]
+    pub fn stale_timeout(mut self, timeout: Duration) -> Self {
+        self.stale_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum duration without receiving any line, comments included, i.e. the idle
+    /// timeout of the stream, or without the other [`IdleActivity`] set with
+    /// [`EventSourceConfig::idle_activity`].
+    ///
+    /// The timer starts once the response is accepted. Once elapsed, the stream yields an
+    /// [`EventError::IdleTimeout`](crate::error::EventError::IdleTimeout) and ends, so a
+    /// [`ReconnectingEventSource`](crate::reconnect::ReconnectingEventSource) reconnects.
+    #[must_use]
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the [`IdleActivity`] resetting the timer of [`EventSourceConfig::idle_timeout`].
+    /// Default to [`IdleActivity::Lines`].
+    #[must_use]
+    pub fn idle_activity(mut self, activity: IdleActivity) -> Self {
+        self.idle_activity = activity;
+        self
+    }
+
+    /// Sets the maximum duration to receive the first event, once the response is accepted.
+    ///
+    /// It catches the servers accepting the connection, then sending nothing, comments aside.
+    /// Once elapsed, the stream yields an
+    /// [`EventError::FirstEventTimeout`](crate::error::EventError::FirstEventTimeout) and ends.
+    #[must_use]
+    pub fn first_event_timeout(mut self, timeout: Duration) -> Self {
+        self.first_event_timeout = Some(timeout);
+        self
+    }
+
+    /// Declares that the server sends a comment, e.g. `: heartbeat`, at least every `interval`.
+    ///
+    /// Once `interval` and `grace` elapsed without any comment since the response was accepted, or
+    /// since the last comment, the stream yields an
+    /// [`EventError::HeartbeatMissed`](crate::error::EventError::HeartbeatMissed) and ends, so a
+    /// [`ReconnectingEventSource`](crate::reconnect::ReconnectingEventSource) reconnects. Unlike
+    /// [`EventSourceConfig::idle_timeout`], the events don't count as heartbeats.
+    #[must_use]
+    pub fn heartbeat(mut self, interval: Duration, grace: Duration) -> Self {
+        self.heartbeat = Some(interval.saturating_add(grace));
+        self
+    }
+
+    /// Sets the maximum duration of the stream, e.g. to consume a feed for a bounded time.
+    ///
+    /// Once elapsed, the stream ends without error, after the events already received, i.e. as
+    /// long as lines can be read without waiting, and until the task must yield to the runtime so
+    /// that a source never waiting still ends. The event being received, if any, is discarded.
+    /// The duration of a [`ReconnectingEventSource`](crate::reconnect::ReconnectingEventSource)
+    /// covers all its connections, and the waits in between.
+    #[must_use]
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Sets the [`ParseMode`]. Default to [`ParseMode::Strict`].
+    #[must_use]
+    pub fn parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = parse_mode;
+        self
+    }
+
+    /// Sets the [`TruncatedEventPolicy`]. Default to [`TruncatedEventPolicy::Discard`] in
+    /// [`ParseMode::Strict`], and [`TruncatedEventPolicy::Dispatch`] in [`ParseMode::Lenient`].
+    #[must_use]
+    pub fn truncated_event(mut self, policy: TruncatedEventPolicy) -> Self {
+        self.truncated_event = Some(policy);
+        self
+    }
+
+    /// Sets the [`InvalidUtf8Policy`]. Default to [`InvalidUtf8Policy::Strict`].
+    #[must_use]
+    pub fn invalid_utf8(mut self, policy: InvalidUtf8Policy) -> Self {
+        self.invalid_utf8 = policy;
+        self
+    }
+
+    /// Sets the maximum length of a line in bytes, terminator excluded. Unlimited by default.
+    ///
+    /// Bounds the memory used by a server never sending a line terminator.
+    #[must_use]
+    pub fn max_line_length(mut self, max_length: usize) -> Self {
+        self.max_line_length = Some(max_length);
+        self
+    }
+
+    /// Sets the [`LongLinePolicy`]. Default to [`LongLinePolicy::Terminate`].
+    #[must_use]
+    pub fn long_line(mut self, policy: LongLinePolicy) -> Self {
+        self.long_line = policy;
+        self
+    }
+
+    /// Sets the maximum size of an event's data in bytes, line feeds between `data` lines included.
+    /// Unlimited by default.
+    ///
+    /// Once exceeded, the stream yields an
+    /// [`EventError::EventTooLarge`](crate::error::EventError::EventTooLarge), discards the event,
+    /// and continues with the next one.
+    #[must_use]
+    pub fn max_event_size(mut self, max_size: usize) -> Self {
+        self.max_event_size = Some(max_size);
+        self
+    }
+
+    /// Sets the capacity in bytes of the buffer the response body is read through.
+    ///
+    /// By default, the body is parsed straight from the chunks received from the connection,
+    /// whatever their size. With a capacity, the chunks are read through a buffer of this size,
+    /// bounding the bytes handed to the parser at once. The buffering of the connection itself is
+    /// set on the reqwest [`Client`](reqwest::Client).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is zero.
+    #[must_use]
+    pub fn read_buffer_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "read buffer capacity must not be zero");
+        self.read_buffer_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets whether fields unknown by the parser, e.g. `seq: 42`, are collected in
+    /// [`Event::extra`](crate::Event::extra) instead of being ignored. Disabled by default.
+    #[must_use]
+    pub fn unknown_fields(mut self, enabled: bool) -> Self {
+        self.unknown_fields = enabled;
+        self
+    }
+
+    /// Only dispatches the events of the given types, filtering the others out in the parser.
+    ///
+    /// The `data` lines of a filtered-out event aren't accumulated once its `event` field is read,
+    /// so it costs no allocation, unlike a filter applied on the stream. Until then, and for the
+    /// events without `event` field, of the default `message` type, the data is accumulated as
+    /// usual, the type being only known at the end of the event. A later `event` field accepting
+    /// the event again dispatches it, unless some of its data was already left out. The `id` and
+    /// `retry` fields of the filtered-out events are still applied.
+    #[must_use]
+    pub fn event_types(mut self, event_types: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.event_types = Some(event_types.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Checks if the events of the given type are dispatched.
+    pub(crate) fn is_accepted_event_type(&self, event_type: &str) -> bool {
+        self.event_types
+            .as_ref()
+            .is_none_or(|event_types| event_types.iter().any(|accepted| accepted == event_type))
+    }
+
+    /// Sets whether the lines of each event are captured in [`Event::raw`](crate::Event::raw), to
+    /// debug malformed streams. Disabled by default.
+    #[must_use]
+    pub fn raw(mut self, enabled: bool) -> Self {
+        self.raw = enabled;
+        self
+    }
+
+    /// Sets whether each event is stamped with the time of its dispatch in
+    /// [`Event::received_at`](crate::Event::received_at), e.g. for latency analysis. Disabled by
+    /// default.
+    #[must_use]
+    pub fn received_at(mut self, enabled: bool) -> Self {
+        self.received_at = enabled;
+        self
+    }
+
+    /// Sets whether each event is numbered in [`Event::sequence`](crate::Event::sequence), e.g. to
+    /// detect events reordered or lost downstream. Disabled by default.
+    #[must_use]
+    pub fn sequence(mut self, enabled: bool) -> Self {
+        self.sequence = enabled;
+        self
+    }
+
+    /// Sets a callback receiving the [`ParseWarning`]s, about malformed input ignored by the
+    /// parser, e.g. to log them. They don't interrupt the stream.
+    ///
+    /// With the `tracing` feature, warnings are also emitted as `tracing` events.
+    #[must_use]
+    pub fn on_warning(mut self, handler: impl Fn(ParseWarning) + Send + Sync + 'static) -> Self {
+        self.on_warning = Some(WarningHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Treats the field `alias` as the given [`Field`], e.g. `message` as [`Field::Data`].
+    ///
+    /// Like field names, aliases are case-insensitive in [`ParseMode::Lenient`].
+    #[must_use]
+    pub fn field_alias(mut self, alias: impl Into<String>, field: Field) -> Self {
+        self.aliases.push((alias.into(), field));
+        self
+    }
+
+    /// Sets the predicate telling if a response status is accepted. Default to `200 OK` only.
+    ///
+    /// Rejected responses fail with
+    /// [`EventSourceError::BadStatus`](crate::error::EventSourceError::BadStatus).
+    #[must_use]
+    pub fn accept_status(
+        mut self,
+        predicate: impl Fn(StatusCode) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.accept_status = Some(StatusPredicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Reads up to `max_size` bytes of the body of responses with a rejected status, to carry
+    /// them in [`EventSourceError::BadStatus`](crate::error::EventSourceError::BadStatus), e.g. to
+    /// debug an authentication failure. Disabled by default.
+    #[must_use]
+    pub fn error_body(mut self, max_size: usize) -> Self {
+        self.error_body = Some(max_size);
+        self
+    }
+
+    /// Accepts any `2xx` response status, e.g. `201 Created`.
+    #[must_use]
+    pub fn accept_any_success(self) -> Self {
+        self.accept_status(|status| status.is_success())
+    }
+
+    /// Sets the predicate telling if a response MIME type is accepted, given without its
+    /// parameters, e.g. `text/event-stream` for `text/event-stream; charset=utf-8`. Default to
+    /// `text/event-stream` only, ignoring its case.
+    ///
+    /// Rejected responses fail with
+    /// [`EventSourceError::BadContentType`](crate::error::EventSourceError::BadContentType), as
+    /// well as those without content type.
+    #[must_use]
+    pub fn accept_content_type(
+        mut self,
+        predicate: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.accept_content_type = Some(ContentTypePredicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Accepts the given MIME types, ignoring their case, e.g. `application/event-stream`.
+    #[must_use]
+    pub fn accept_content_types<I, T>(self, content_types: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let content_types = content_types
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<String>>();
+        self.accept_content_type(move |essence| {
+            content_types
+                .iter()
+                .any(|content_type| content_type.eq_ignore_ascii_case(essence))
+        })
+    }
+
+    /// Skips the content type check entirely, accepting responses whatever their content type, or
+    /// without one, e.g. an event stream served as `text/plain`.
+    ///
+    /// The body is still parsed as an event stream, so anything else produces garbage events.
+    #[must_use]
+    pub fn danger_accept_any_content_type(mut self) -> Self {
+        self.any_content_type = true;
+        self
+    }
+
+    /// Checks if the response MIME type is accepted.
+    pub(crate) fn is_accepted_content_type(&self, essence: &str) -> bool {
+        match &self.accept_content_type {
+            Some(predicate) => (predicate.0)(essence),
+            None => crate::is_event_stream(essence),
+        }
+    }
+
+    /// Sets a custom check of the response, run after the status and content type ones, e.g. to
+    /// require a protocol version header. The body isn't read yet.
+    ///
+    /// Returning an error message rejects the response with
+    /// [`EventSourceError::Rejected`](crate::error::EventSourceError::Rejected).
+    #[must_use]
+    pub fn validate(
+        mut self,
+        validator: impl Fn(&Response) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Validator(Arc::new(validator)));
+        self
+    }
+
+    /// Checks if the response status is accepted.
+    pub(crate) fn is_accepted(&self, status: StatusCode) -> bool {
+        match &self.accept_status {
+            Some(predicate) => (predicate.0)(status),
+            None => status == StatusCode::OK,
+        }
+    }
+
+    /// Sets the [`CancellationToken`] ending the stream when cancelled.
+    #[must_use]
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Returns the [`TruncatedEventPolicy`] in effect.
+    pub(crate) fn truncated_event_policy(&self) -> TruncatedEventPolicy {
+        self.truncated_event.unwrap_or(match self.parse_mode {
+            ParseMode::Strict => TruncatedEventPolicy::Discard,
+            ParseMode::Lenient => TruncatedEventPolicy::Dispatch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_field_names_according_to_mode() {
+        let strict = EventSourceConfig::new();
+        let lenient = EventSourceConfig::new().parse_mode(ParseMode::Lenient);
+        assert_eq!(Field::parse("data", &strict), Some(Field::Data));
+        assert_eq!(Field::parse("Data", &strict), None);
+        assert_eq!(Field::parse("Data", &lenient), Some(Field::Data));
+        assert_eq!(Field::parse("RETRY", &lenient), Some(Field::Retry));
+        assert_eq!(Field::parse("unknown", &lenient), None);
+    }
+
+    #[test]
+    fn accept_status_according_to_predicate() {
+        let config = EventSourceConfig::new();
+        assert!(config.is_accepted(StatusCode::OK));
+        assert!(!config.is_accepted(StatusCode::CREATED));
+
+        let config = config.accept_any_success();
+        assert!(config.is_accepted(StatusCode::CREATED));
+        assert!(!config.is_accepted(StatusCode::MULTIPLE_CHOICES));
+
+        let config = config.accept_status(|status| status == StatusCode::ACCEPTED);
+        assert!(config.is_accepted(StatusCode::ACCEPTED));
+        assert!(!config.is_accepted(StatusCode::OK));
+    }
+
+    #[test]
+    fn accept_content_types_from_list() {
+        let config = EventSourceConfig::new();
+        assert!(config.is_accepted_content_type("text/event-stream"));
+        assert!(!config.is_accepted_content_type("application/event-stream"));
+
+        let config = config.accept_content_types(["text/event-stream", "application/event-stream"]);
+        assert!(config.is_accepted_content_type("Application/Event-Stream"));
+        assert!(!config.is_accepted_content_type("text/plain"));
+    }
+
+    #[test]
+    fn match_field_aliases() {
+        let config = EventSourceConfig::new()
+            .field_alias("message", Field::Data)
+            .field_alias("data", Field::Event);
+        assert_eq!(Field::parse("message", &config), Some(Field::Data));
+        assert_eq!(Field::parse("data", &config), Some(Field::Event));
+        assert_eq!(Field::parse("Message", &config), None);
+
+        let config = config.parse_mode(ParseMode::Lenient);
+        assert_eq!(Field::parse("Message", &config), Some(Field::Data));
+    }
+}
@@ -0,0 +1,185 @@
+//! Accumulation of the text deltas sent by streaming chat APIs.
+//!
+//! Enabled by the `json` feature. Streaming chat APIs send the generated text as partial deltas,
+//! one per event. A [`Deltas`] stream extracts the delta of each event, with a closure or a JSON
+//! pointer, yields it, and accumulates the assembled text.
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::{EventSource, delta::Deltas};
+//!
+//! # async fn run() {
+//! let events = reqwest::get("https://sse.test-free.online/api/story")
+//!     .await.unwrap()
+//!     .events()
+//!     .await.unwrap()
+//!     .until_done();
+//! let mut deltas = Deltas::json_pointer(events, "/choices/0/delta/content");
+//!
+//! while let Some(Ok(delta)) = deltas.next().await {
+//!     print!("{delta}");
+//! }
+//! println!("\n{}", deltas.text());
+//! # }
+//! ```
+use std::{
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures_core::Stream;
+use serde_json::Value;
+
+use crate::{Event, error::EventError};
+
+type Extract = Box<dyn FnMut(&Event) -> Result<Option<String>, EventError> + Send>;
+
+/// Stream of the text deltas extracted from the events of the wrapped stream, accumulating them.
+///
+/// Events without delta are skipped, while errors of the wrapped stream and of the extraction are
+/// yielded as is.
+pub struct Deltas<S> {
+    stream: S,
+    extract: Extract,
+    text: String,
+}
+
+impl<S> Deltas<S> {
+    /// Wraps the stream of events, extracting their delta with the closure.
+    ///
+    /// The closure returns `None` for events without delta.
+    pub fn new<F>(stream: S, extract: F) -> Self
+    where
+        F: FnMut(&Event) -> Result<Option<String>, EventError> + Send + 'static,
+    {
+        Self {
+            stream,
+            extract: Box::new(extract),
+            text: String::new(),
+        }
+    }
+
+    /// Wraps the stream of events, extracting their delta from the JSON string pointed to in
+    /// their data, as described by [RFC 6901](https://datatracker.ietf.org/doc/html/rfc6901).
+    ///
+    /// Events whose data isn't JSON are reported as [`EventError::JsonError`], while those without
+    /// string at the pointer have no delta.
+    pub fn json_pointer(stream: S, pointer: impl Into<String>) -> Self {
+        let pointer = pointer.into();
+        Self::new(stream, move |event| {
+            let value = event.json::<Value>().map_err(EventError::JsonError)?;
+            Ok(value
+                .pointer(&pointer)
+                .and_then(Value::as_str)
+                .map(str::to_string))
+        })
+    }
+
+    /// Returns the text accumulated so far.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the text accumulated so far, dropping the stream.
+    #[must_use]
+    pub fn into_text(self) -> String {
+        self.text
+    }
+}
+
+impl<S> Deltas<S>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+{
+    /// Consumes the rest of the stream, and returns the assembled text.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error yielded by the stream.
+    pub async fn finish(mut self) -> Result<String, EventError> {
+        while let Some(delta) = std::future::poll_fn(|cx| Pin::new(&mut self).poll_next(cx)).await {
+            delta?;
+        }
+        Ok(self.text)
+    }
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for Deltas<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deltas")
+            .field("stream", &self.stream)
+            .field("text", &self.text)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Stream for Deltas<S>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+{
+    type Item = Result<String, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            let event = match ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+                Some(Ok(event)) => event,
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => return Poll::Ready(None),
+            };
+            match (this.extract)(&event) {
+                Ok(Some(delta)) => {
+                    this.text.push_str(&delta);
+                    return Poll::Ready(Some(Ok(delta)));
+                }
+                Ok(None) => {}
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::event;
+
+    #[tokio::test]
+    async fn accumulate_deltas_at_json_pointer() {
+        let events = tokio_stream::iter(vec![
+            Ok(event(r#"{"choices":[{"delta":{"role":"assistant"}}]}"#)),
+            Ok(event(r#"{"choices":[{"delta":{"content":"Hel"}}]}"#)),
+            Ok(event(r#"{"choices":[{"delta":{"content":"lo"}}]}"#)),
+        ]);
+        let mut deltas = Deltas::json_pointer(events, "/choices/0/delta/content");
+
+        assert_eq!(deltas.next().await.unwrap().unwrap(), "Hel");
+        assert_eq!(deltas.text(), "Hel");
+        assert_eq!(deltas.finish().await.unwrap(), "Hello");
+    }
+
+    #[tokio::test]
+    async fn accumulate_deltas_extracted_by_closure() {
+        let events = tokio_stream::iter(vec![Ok(event("a")), Ok(event("")), Ok(event("b"))]);
+        let deltas = Deltas::new(events, |event| {
+            Ok(Some(event.data.clone()).filter(|data| !data.is_empty()))
+        });
+
+        assert_eq!(deltas.finish().await.unwrap(), "ab");
+    }
+
+    #[tokio::test]
+    async fn report_invalid_json() {
+        let events = tokio_stream::iter(vec![Ok(event("nope"))]);
+        let deltas = Deltas::json_pointer(events, "/content");
+
+        assert!(matches!(
+            deltas.finish().await,
+            Err(EventError::JsonError(error)) if error.data() == "nope"
+        ));
+    }
+}
@@ -0,0 +1,171 @@
+//! Ending a stream at a sentinel message.
+//!
+//! Some APIs, like those of LLM providers, end their streams with a sentinel event, e.g.
+//! `data: [DONE]`, rather than by closing the connection. An [`UntilDone`] stream ends cleanly at
+//! the first event whose data is the sentinel, without yielding it, and drops the stream it wraps.
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::EventSource;
+//!
+//! # async fn run() {
+//! let mut chunks = reqwest::get("https://sse.test-free.online/api/story")
+//!     .await.unwrap()
+//!     .events()
+//!     .await.unwrap()
+//!     .until_done();
+//!
+//! while let Some(Ok(chunk)) = chunks.next().await {
+//!     println!("{}", chunk.data);
+//! }
+//! # }
+//! ```
+use std::{
+    borrow::Cow,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures_core::{Stream, stream::FusedStream};
+
+use crate::{Event, error::EventError, stream::EventStream};
+
+/// Sentinel used by OpenAI-style APIs.
+pub const DONE: &str = "[DONE]";
+
+/// Stream adapter ending at the sentinel event.
+#[derive(Debug)]
+pub struct UntilDone<S> {
+    /// The wrapped stream, dropped once the sentinel is received, or once it ends.
+    stream: Option<S>,
+    sentinel: Cow<'static, str>,
+    /// Whether the sentinel has been received.
+    done: bool,
+}
+
+impl<S> UntilDone<S> {
+    /// Wraps the stream, ending at the [`DONE`] sentinel.
+    #[must_use]
+    pub fn new(stream: S) -> Self {
+        Self::with_sentinel(stream, DONE)
+    }
+
+    /// Wraps the stream, ending at the given sentinel.
+    #[must_use]
+    pub fn with_sentinel(stream: S, sentinel: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            stream: Some(stream),
+            sentinel: sentinel.into(),
+            done: false,
+        }
+    }
+
+    /// Checks if the sentinel has been received, as opposed to the wrapped stream ending without
+    /// it, e.g. when the connection is lost.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns the wrapped stream, or `None` if the sentinel has been received or the stream has
+    /// ended.
+    pub fn into_inner(self) -> Option<S> {
+        self.stream
+    }
+}
+
+impl<S> Stream for UntilDone<S>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let Some(stream) = &mut this.stream else {
+            return Poll::Ready(None);
+        };
+        match ready!(Pin::new(stream).poll_next(cx)) {
+            Some(Ok(event)) if event.data == this.sentinel => {
+                this.stream = None;
+                this.done = true;
+                Poll::Ready(None)
+            }
+            None => {
+                this.stream = None;
+                Poll::Ready(None)
+            }
+            item => Poll::Ready(item),
+        }
+    }
+}
+
+/// Once the sentinel is received, or the wrapped stream has ended, the stream keeps returning
+/// `None`.
+impl<S> FusedStream for UntilDone<S>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+{
+    fn is_terminated(&self) -> bool {
+        self.stream.is_none()
+    }
+}
+
+impl EventStream {
+    /// Ends the stream at the [`DONE`] sentinel, see [`UntilDone`].
+    #[must_use]
+    pub fn until_done(self) -> UntilDone<Self> {
+        UntilDone::new(self)
+    }
+
+    /// Ends the stream at the given sentinel, see [`UntilDone`].
+    #[must_use]
+    pub fn until_sentinel(self, sentinel: impl Into<Cow<'static, str>>) -> UntilDone<Self> {
+        UntilDone::with_sentinel(self, sentinel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::event;
+
+    #[tokio::test]
+    async fn end_at_sentinel() {
+        let events = tokio_stream::iter(vec![Ok(event("a")), Ok(event("[DONE]")), Ok(event("b"))]);
+        let mut events = UntilDone::new(events);
+
+        assert_eq!(events.next().await.unwrap().unwrap().data, "a");
+        assert!(events.next().await.is_none());
+        assert!(events.is_done());
+        assert!(events.is_terminated());
+        assert!(events.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn end_without_sentinel() {
+        let mut events = UntilDone::new(tokio_stream::iter(vec![Ok(event("a"))]));
+
+        assert_eq!(events.next().await.unwrap().unwrap().data, "a");
+        assert!(!events.is_terminated());
+        assert!(events.next().await.is_none());
+        assert!(events.is_terminated());
+        assert!(!events.is_done());
+        assert!(events.into_inner().is_none());
+    }
+
+    #[tokio::test]
+    async fn end_at_custom_sentinel() {
+        let events =
+            tokio_stream::iter(vec![Ok(event("[DONE]")), Ok(event("bye")), Ok(event("b"))]);
+        let data = UntilDone::with_sentinel(events, "bye")
+            .map(|event| event.unwrap().data)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(data, vec!["[DONE]"]);
+    }
+}
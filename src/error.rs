@@ -2,9 +2,70 @@ use std::fmt::Display;
 
 use reqwest::{StatusCode, header::HeaderValue};
 
+use crate::{Event, breaker::CircuitState};
+
 #[derive(Debug)]
 pub enum EventError {
     IoError(std::io::Error),
+    RequestError(reqwest::Error),
+    SourceError(EventSourceError),
+    CircuitOpen(CircuitState),
+    /// No data within the
+    /// [`EventSourceConfig::stale_timeout`](crate::config::EventSourceConfig::stale_timeout).
+    StaleConnection,
+    /// No activity within the
+    /// [`EventSourceConfig::idle_timeout`](crate::config::EventSourceConfig::idle_timeout).
+    IdleTimeout,
+    /// No event within the
+    /// [`EventSourceConfig::first_event_timeout`](crate::config::EventSourceConfig::first_event_timeout).
+    FirstEventTimeout,
+    /// No heartbeat comment within the interval and grace declared with
+    /// [`EventSourceConfig::heartbeat`](crate::config::EventSourceConfig::heartbeat).
+    HeartbeatMissed,
+    TruncatedEvent(Box<Event>),
+    Utf8Error(std::str::Utf8Error),
+    LineTooLong(usize),
+    EventTooLarge(usize),
+    /// No response within the
+    /// [`EventSourceConfig::connect_timeout`](crate::config::EventSourceConfig::connect_timeout).
+    ConnectTimeout,
+    #[cfg(feature = "json")]
+    JsonError(JsonError),
+    #[cfg(feature = "json")]
+    UnknownEventType(String),
+    #[cfg(feature = "graphql")]
+    GraphQlErrors(Vec<crate::graphql::GraphQlError>),
+    #[cfg(feature = "base64")]
+    Base64Error(base64::DecodeError),
+    /// Failure to decode a binary payload, with the error of the decode function.
+    #[cfg(feature = "base64")]
+    DecodeError(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl EventError {
+    /// Returns whether the error is due to a timeout, e.g. to retry the connection: one of the
+    /// timeouts of [`EventSourceConfig`](crate::config::EventSourceConfig), or a timeout of the
+    /// client, reported as an [`EventError::RequestError`] or, while reading the body, as an
+    /// [`EventError::IoError`].
+    #[must_use]
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            EventError::StaleConnection
+            | EventError::IdleTimeout
+            | EventError::FirstEventTimeout
+            | EventError::HeartbeatMissed
+            | EventError::ConnectTimeout => true,
+            EventError::RequestError(error) => error.is_timeout(),
+            EventError::IoError(error) => {
+                error.kind() == std::io::ErrorKind::TimedOut
+                    || error
+                        .get_ref()
+                        .and_then(|error| error.downcast_ref::<reqwest::Error>())
+                        .is_some_and(reqwest::Error::is_timeout)
+            }
+            _ => false,
+        }
+    }
 }
 
 impl Display for EventError {
@@ -13,21 +74,136 @@ impl Display for EventError {
             EventError::IoError(error) => {
                 write!(f, "failed to process event due to I/O error: {error}")
             }
+            EventError::RequestError(error) => {
+                write!(f, "failed to connect to event source: {error}")
+            }
+            EventError::SourceError(error) => {
+                write!(f, "event source rejected the connection: {error}")
+            }
+            EventError::CircuitOpen(state) => {
+                write!(
+                    f,
+                    "connection attempt rejected by circuit breaker: {state:?}"
+                )
+            }
+            EventError::StaleConnection => {
+                write!(f, "no data received in time, connection is stale")
+            }
+            EventError::IdleTimeout => {
+                write!(f, "no activity in time, event stream is idle")
+            }
+            EventError::FirstEventTimeout => {
+                write!(f, "no event received in time after connecting")
+            }
+            EventError::HeartbeatMissed => {
+                write!(f, "no heartbeat received in time from event source")
+            }
+            EventError::TruncatedEvent(event) => {
+                write!(
+                    f,
+                    "stream ended before the end of the `{}` event",
+                    event.event_type
+                )
+            }
+            EventError::Utf8Error(error) => {
+                write!(f, "failed to decode line as UTF-8: {error}")
+            }
+            EventError::LineTooLong(max_length) => {
+                write!(f, "line exceeds the maximum length of {max_length} bytes")
+            }
+            EventError::EventTooLarge(max_size) => {
+                write!(f, "event exceeds the maximum size of {max_size} bytes")
+            }
+            EventError::ConnectTimeout => {
+                write!(f, "no response received in time from event source")
+            }
+            #[cfg(feature = "json")]
+            EventError::JsonError(error) => write!(f, "{error}"),
+            #[cfg(feature = "json")]
+            EventError::UnknownEventType(event_type) => {
+                write!(f, "no variant matches the `{event_type}` event type")
+            }
+            #[cfg(feature = "graphql")]
+            EventError::GraphQlErrors(errors) => {
+                write!(f, "GraphQL operation failed")?;
+                for (index, error) in errors.iter().enumerate() {
+                    let separator = if index == 0 { ':' } else { ';' };
+                    write!(f, "{separator} {}", error.message)?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "base64")]
+            EventError::Base64Error(error) => {
+                write!(f, "failed to decode event data as base64: {error}")
+            }
+            #[cfg(feature = "base64")]
+            EventError::DecodeError(error) => {
+                write!(f, "failed to decode event payload: {error}")
+            }
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Failure to deserialize the data of an event as JSON, with the data.
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub struct JsonError {
+    data: String,
+    error: serde_json::Error,
+}
+
+#[cfg(feature = "json")]
+impl JsonError {
+    pub(crate) fn new(data: String, error: serde_json::Error) -> Self {
+        Self { data, error }
+    }
+
+    /// Returns the data of the event.
+    #[must_use]
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// Returns the error reported by `serde_json`.
+    #[must_use]
+    pub fn error(&self) -> &serde_json::Error {
+        &self.error
+    }
+}
+
+#[cfg(feature = "json")]
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "failed to deserialize event data as JSON: {}, in: {:?}",
+            self.error, self.data
+        )
+    }
+}
+
+#[derive(Debug)]
 pub enum EventSourceError {
-    BadStatus(StatusCode),
+    /// The response status, with the beginning of its body if captured with
+    /// [`EventSourceConfig::error_body`](crate::config::EventSourceConfig::error_body).
+    BadStatus(StatusCode, Option<String>),
     BadContentType(Option<HeaderValue>),
+    Rejected(String),
+    RequestError(reqwest::Error),
+    ConnectTimeout,
 }
 
 impl Display for EventSourceError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            EventSourceError::BadStatus(status_code) => {
-                write!(f, "expecting status code `200`, found: {status_code}")
+            EventSourceError::BadStatus(status_code, None) => {
+                write!(f, "unexpected status code: {status_code}")
+            }
+            EventSourceError::BadStatus(status_code, Some(body)) => {
+                write!(
+                    f,
+                    "unexpected status code: {status_code}, with body: {body}"
+                )
             }
             EventSourceError::BadContentType(None) => {
                 write!(
@@ -49,6 +225,64 @@ impl Display for EventSourceError {
                     }
                 }
             }
+            EventSourceError::Rejected(reason) => {
+                write!(f, "response rejected by validator: {reason}")
+            }
+            EventSourceError::RequestError(error) => {
+                write!(f, "failed to connect to event source: {error}")
+            }
+            EventSourceError::ConnectTimeout => {
+                write!(f, "no response received in time from event source")
+            }
+        }
+    }
+}
+
+/// Reason of a subscription started with [`EventStream::subscribe`](crate::stream::EventStream::subscribe)
+/// stopping before the end of the stream.
+#[derive(Debug)]
+pub enum SubscribeError<E> {
+    /// The stream yielded an error.
+    EventError(EventError),
+    /// The handler failed, with its error.
+    HandlerError(E),
+}
+
+impl<E: Display> Display for SubscribeError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubscribeError::EventError(error) => {
+                write!(f, "failed to receive event: {error}")
+            }
+            SubscribeError::HandlerError(error) => {
+                write!(f, "failed to handle event: {error}")
+            }
+        }
+    }
+}
+
+/// Malformed input ignored by the parser, reported to the handler set with
+/// [`EventSourceConfig::on_warning`](crate::config::EventSourceConfig::on_warning).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// A field unknown by the parser, with its name.
+    UnknownField(String),
+    /// A `retry` field with an invalid value.
+    InvalidRetry(String),
+    /// An `id` field containing a NULL character.
+    NullInId(String),
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseWarning::UnknownField(name) => write!(f, "ignored unknown field `{name}`"),
+            ParseWarning::InvalidRetry(value) => {
+                write!(f, "ignored invalid retry value `{value}`")
+            }
+            ParseWarning::NullInId(value) => {
+                write!(f, "ignored id containing a NULL character: {value:?}")
+            }
         }
     }
 }
@@ -0,0 +1,319 @@
+//! The [GraphQL over Server-Sent Events](https://github.com/enisdenjo/graphql-sse/blob/master/PROTOCOL.md)
+//! protocol.
+//!
+//! Enabled by the `graphql` feature. A [`GraphQlEvents`] stream turns the `next` and `complete`
+//! events of the protocol into typed [`GraphQlMessage`]s, in either mode:
+//!
+//! - In the distinct connections mode, the operation is sent as the body of the request opening
+//!   the stream, and the stream ends with the `complete` event.
+//! - In the single connection mode, a stream is first reserved with [`reserve`], then opened with
+//!   the token in the [`TOKEN_HEADER`] header. Operations are sent in separate requests, and their
+//!   messages are multiplexed on the stream, identified by the `operationId` of the operations.
+//!
+//! ```rust,no_run
+//! use serde_json::{Value, json};
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::{EventSourceRequest, graphql::{GraphQlEvents, GraphQlMessage}};
+//!
+//! # async fn run() {
+//! let events = reqwest::Client::new()
+//!     .post("https://example.com/graphql/stream")
+//!     .json(&json!({ "query": "subscription { greetings }" }))
+//!     .events()
+//!     .await.unwrap();
+//! let mut messages = GraphQlEvents::<_, Value>::distinct(events);
+//!
+//! while let Some(Ok(GraphQlMessage::Next { result, .. })) = messages.next().await {
+//!     println!("{:?}", result.data);
+//! }
+//! # }
+//! ```
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures_core::Stream;
+use reqwest::{Client, IntoUrl};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{
+    Event,
+    error::{EventError, EventSourceError},
+};
+
+/// Header carrying the token of a reserved stream, in the single connection mode.
+pub const TOKEN_HEADER: &str = "x-graphql-event-stream-token";
+
+/// Location of an error in the GraphQL document.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+pub struct Location {
+    /// Line number, starting at 1.
+    pub line: u64,
+    /// Column number, starting at 1.
+    pub column: u64,
+}
+
+/// GraphQL error, as described by the GraphQL specification.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GraphQlError {
+    /// Description of the error, for the developer.
+    pub message: String,
+    /// Locations of the error in the document, if it can be associated with some.
+    #[serde(default)]
+    pub locations: Vec<Location>,
+    /// Path of the response field in error, made of field names and list indices.
+    #[serde(default)]
+    pub path: Vec<Value>,
+    /// Additional information set by the server, e.g. an error code.
+    pub extensions: Option<Value>,
+}
+
+/// Result of the execution of an operation, possibly partial.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(bound = "T: DeserializeOwned")]
+pub struct ExecutionResult<T> {
+    /// Data of the operation, `None` if it couldn't be executed.
+    pub data: Option<T>,
+    /// Errors raised during the execution, possibly along partial data.
+    #[serde(default)]
+    pub errors: Vec<GraphQlError>,
+    /// Additional information set by the server, e.g. tracing data.
+    pub extensions: Option<Value>,
+}
+
+/// Message of the protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphQlMessage<T> {
+    /// Result of the operation, `id` being the operation ID in the single connection mode.
+    Next {
+        /// Operation ID, in the single connection mode.
+        id: Option<String>,
+        /// Result of the operation.
+        result: ExecutionResult<T>,
+    },
+    /// End of the operation, `id` being the operation ID in the single connection mode.
+    Complete {
+        /// Operation ID, in the single connection mode.
+        id: Option<String>,
+    },
+}
+
+/// Mode of the protocol, see the [module documentation](self).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ConnectionMode {
+    Distinct,
+    Single,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "T: DeserializeOwned")]
+struct Envelope<T> {
+    id: String,
+    payload: Option<T>,
+}
+
+/// Stream of the protocol messages read from the events of the wrapped stream.
+///
+/// Events of other types are skipped. Results carrying errors but no data, which report operations
+/// that couldn't be executed, are yielded as [`EventError::GraphQlErrors`].
+#[derive(Debug)]
+pub struct GraphQlEvents<S, T> {
+    /// The wrapped stream, dropped once the operation is complete in the distinct connections mode.
+    stream: Option<S>,
+    mode: ConnectionMode,
+    data: PhantomData<fn() -> T>,
+}
+
+impl<S, T> GraphQlEvents<S, T> {
+    /// Wraps the stream of a single operation, in the distinct connections mode.
+    #[must_use]
+    pub fn distinct(stream: S) -> Self {
+        Self::new(stream, ConnectionMode::Distinct)
+    }
+
+    /// Wraps the stream of a reserved connection, in the single connection mode.
+    #[must_use]
+    pub fn single_connection(stream: S) -> Self {
+        Self::new(stream, ConnectionMode::Single)
+    }
+
+    fn new(stream: S, mode: ConnectionMode) -> Self {
+        Self {
+            stream: Some(stream),
+            mode,
+            data: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped stream, or `None` if the operation is complete in the distinct
+    /// connections mode.
+    pub fn into_inner(self) -> Option<S> {
+        self.stream
+    }
+}
+
+impl<S, T: DeserializeOwned> GraphQlEvents<S, T> {
+    fn message(&self, event: &Event) -> Result<Option<GraphQlMessage<T>>, EventError> {
+        let (id, result) = match (&*event.event_type, self.mode) {
+            ("next", ConnectionMode::Distinct) => (None, event.json().map(Some)),
+            ("next", ConnectionMode::Single) => event
+                .json::<Envelope<ExecutionResult<T>>>()
+                .map(|envelope| (Some(envelope.id), Ok(envelope.payload)))
+                .map_err(EventError::JsonError)?,
+            ("complete", ConnectionMode::Distinct) => {
+                return Ok(Some(GraphQlMessage::Complete { id: None }));
+            }
+            ("complete", ConnectionMode::Single) => {
+                let envelope = event
+                    .json::<Envelope<Value>>()
+                    .map_err(EventError::JsonError)?;
+                return Ok(Some(GraphQlMessage::Complete {
+                    id: Some(envelope.id),
+                }));
+            }
+            _ => return Ok(None),
+        };
+        match result.map_err(EventError::JsonError)? {
+            Some(result) if result.data.is_none() && !result.errors.is_empty() => {
+                Err(EventError::GraphQlErrors(result.errors))
+            }
+            Some(result) => Ok(Some(GraphQlMessage::Next { id, result })),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<S, T> Stream for GraphQlEvents<S, T>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+    T: DeserializeOwned,
+{
+    type Item = Result<GraphQlMessage<T>, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while let Some(stream) = &mut this.stream {
+            let event = match ready!(Pin::new(stream).poll_next(cx)) {
+                Some(Ok(event)) => event,
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => break,
+            };
+            match this.message(&event) {
+                Ok(Some(message)) => {
+                    if this.mode == ConnectionMode::Distinct
+                        && matches!(message, GraphQlMessage::Complete { .. })
+                    {
+                        this.stream = None;
+                    }
+                    return Poll::Ready(Some(Ok(message)));
+                }
+                Ok(None) => {}
+                Err(error) => return Poll::Ready(Some(Err(error))),
+            }
+        }
+        this.stream = None;
+        Poll::Ready(None)
+    }
+}
+
+/// Reserves a stream for the single connection mode, returning its token.
+///
+/// # Errors
+///
+/// Returns an [`EventSourceError`] if the request fails or is rejected by the server.
+pub async fn reserve(client: &Client, url: impl IntoUrl) -> Result<String, EventSourceError> {
+    let response = client
+        .put(url)
+        .send()
+        .await
+        .map_err(EventSourceError::RequestError)?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(EventSourceError::RequestError)?;
+    if status.is_success() {
+        Ok(body)
+    } else {
+        Err(EventSourceError::BadStatus(status, Some(body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::event_of_type as event;
+
+    #[tokio::test]
+    async fn end_at_complete_in_distinct_mode() {
+        let events = tokio_stream::iter(vec![
+            Ok(event("next", r#"{"data":{"greeting":"hi"}}"#)),
+            Ok(event("message", "ignored")),
+            Ok(event("complete", "")),
+            Ok(event("next", r#"{"data":{"greeting":"late"}}"#)),
+        ]);
+        let messages = GraphQlEvents::<_, Value>::distinct(events)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(
+            &messages[0],
+            GraphQlMessage::Next { id: None, result } if result.data.as_ref().unwrap()["greeting"] == "hi"
+        ));
+        assert_eq!(messages[1], GraphQlMessage::Complete { id: None });
+    }
+
+    #[tokio::test]
+    async fn identify_operations_in_single_connection_mode() {
+        let events = tokio_stream::iter(vec![
+            Ok(event("next", r#"{"id":"a","payload":{"data":1}}"#)),
+            Ok(event("complete", r#"{"id":"a"}"#)),
+            Ok(event("next", r#"{"id":"b","payload":{"data":2}}"#)),
+        ]);
+        let messages = GraphQlEvents::<_, u32>::single_connection(events)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(
+            &messages[0],
+            GraphQlMessage::Next { id: Some(id), result } if id == "a" && result.data == Some(1)
+        ));
+        assert_eq!(
+            messages[1],
+            GraphQlMessage::Complete {
+                id: Some("a".to_string())
+            }
+        );
+        assert!(matches!(
+            &messages[2],
+            GraphQlMessage::Next { id: Some(id), result } if id == "b" && result.data == Some(2)
+        ));
+    }
+
+    #[tokio::test]
+    async fn report_results_without_data() {
+        let events = tokio_stream::iter(vec![Ok(event(
+            "next",
+            r#"{"errors":[{"message":"unknown field","locations":[{"line":1,"column":3}]}]}"#,
+        ))]);
+        let mut messages = GraphQlEvents::<_, Value>::distinct(events);
+
+        match messages.next().await {
+            Some(Err(EventError::GraphQlErrors(errors))) => {
+                assert_eq!(errors[0].message, "unknown field");
+                assert_eq!(errors[0].locations, vec![Location { line: 1, column: 3 }]);
+            }
+            item => panic!("unexpected item: {item:?}"),
+        }
+    }
+}
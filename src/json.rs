@@ -0,0 +1,257 @@
+//! Deserialization of the data of events as JSON.
+//!
+//! Enabled by the `json` feature. [`Event::json`] deserializes the data of a single event, and a
+//! [`JsonEvents`] stream deserializes the data of each event of the stream it wraps into a typed
+//! payload, with [`serde_json`].
+//!
+//! ```rust,no_run
+//! use serde::Deserialize;
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::{EventSource, json::InvalidJsonPolicy};
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Story {
+//!     title: String,
+//! }
+//!
+//! # async fn run() {
+//! let mut stories = reqwest::get("https://sse.test-free.online/api/story")
+//!     .await.unwrap()
+//!     .events()
+//!     .await.unwrap()
+//!     .json_events::<Story>(InvalidJsonPolicy::Skip);
+//!
+//! while let Some(Ok(story)) = stories.next().await {
+//!     println!("{}", story.title);
+//! }
+//! # }
+//! ```
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    Event,
+    error::{EventError, JsonError},
+    stream::EventStream,
+};
+
+/// Policy applied to the events whose data can't be deserialized.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum InvalidJsonPolicy {
+    /// The stream yields an [`EventError::JsonError`], and goes on.
+    #[default]
+    Error,
+    /// The event is skipped.
+    Skip,
+}
+
+/// Stream of the payloads deserialized from the data of the events of the wrapped stream.
+///
+/// Errors of the wrapped stream are forwarded as is.
+#[derive(Debug)]
+pub struct JsonEvents<S, T> {
+    stream: S,
+    policy: InvalidJsonPolicy,
+    payload: PhantomData<fn() -> T>,
+}
+
+impl<S, T> JsonEvents<S, T> {
+    /// Wraps the stream of events, applying the given [`InvalidJsonPolicy`].
+    #[must_use]
+    pub fn new(stream: S, policy: InvalidJsonPolicy) -> Self {
+        Self {
+            stream,
+            policy,
+            payload: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S, T> Stream for JsonEvents<S, T>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+    T: DeserializeOwned,
+{
+    type Item = Result<T, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let event = match ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+                Some(Ok(event)) => event,
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => return Poll::Ready(None),
+            };
+            match event.json() {
+                Ok(payload) => return Poll::Ready(Some(Ok(payload))),
+                Err(error) => match self.policy {
+                    InvalidJsonPolicy::Error => {
+                        return Poll::Ready(Some(Err(EventError::JsonError(error))));
+                    }
+                    InvalidJsonPolicy::Skip => {}
+                },
+            }
+        }
+    }
+}
+
+/// Conversion of an event into a typed value, usually an enum with a variant per event type.
+///
+/// With the `derive` feature, it can be derived with [`reqwest_sse::SseEvent`](crate::SseEvent):
+///
+/// ```rust,ignore
+/// #[derive(SseEvent)]
+/// enum Message {
+///     #[sse(rename = "user-joined")]
+///     Joined(User),
+///     Ping,
+///     #[sse(other)]
+///     Unknown,
+/// }
+/// ```
+///
+/// Each variant matches the events whose type is its name or the `rename` one. The data is
+/// deserialized from JSON into the field of newtype variants, and ignored by unit variants.
+/// Events matched by no variant are reported as [`EventError::UnknownEventType`], unless a unit
+/// variant is marked with `#[sse(other)]`.
+pub trait SseEvent: Sized {
+    /// Converts the event.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EventError`] if the event doesn't map to a value.
+    fn from_event(event: &Event) -> Result<Self, EventError>;
+}
+
+/// Stream of the values converted from the events of the wrapped stream, see [`SseEvent`].
+///
+/// Errors of the wrapped stream, and of the conversions, are yielded as is.
+#[derive(Debug)]
+pub struct TypedEvents<S, T> {
+    stream: S,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<S, T> TypedEvents<S, T> {
+    /// Wraps the stream of events.
+    #[must_use]
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            value: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S, T> Stream for TypedEvents<S, T>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+    T: SseEvent,
+{
+    type Item = Result<T, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(Pin::new(&mut self.stream).poll_next(cx));
+        Poll::Ready(item.map(|event| T::from_event(&event?)))
+    }
+}
+
+impl Event {
+    /// Deserializes the data of the event as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`JsonError`], with a copy of the data, if it isn't a valid JSON `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, JsonError> {
+        serde_json::from_str(&self.data).map_err(|error| JsonError::new(self.data.clone(), error))
+    }
+}
+
+impl EventStream {
+    /// Deserializes the data of each event into a `T`, see [`JsonEvents`].
+    #[must_use]
+    pub fn json_events<T: DeserializeOwned>(
+        self,
+        policy: InvalidJsonPolicy,
+    ) -> JsonEvents<Self, T> {
+        JsonEvents::new(self, policy)
+    }
+
+    /// Converts each event into a `T`, see [`SseEvent`].
+    #[must_use]
+    pub fn typed<T: SseEvent>(self) -> TypedEvents<Self, T> {
+        TypedEvents::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::event;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Payload {
+        id: u32,
+    }
+
+    #[test]
+    fn deserialize_data() {
+        assert_eq!(
+            event(r#"{"id":1}"#).json::<Payload>().unwrap(),
+            Payload { id: 1 }
+        );
+
+        let error = event("{").json::<Payload>().unwrap_err();
+        assert_eq!(error.data(), "{");
+        assert!(error.error().is_eof());
+    }
+
+    #[tokio::test]
+    async fn yield_json_errors_by_default() {
+        let events = tokio_stream::iter(vec![
+            Ok(event(r#"{"id":1}"#)),
+            Ok(event("nope")),
+            Ok(event(r#"{"id":2}"#)),
+        ]);
+        let payloads = JsonEvents::<_, Payload>::new(events, InvalidJsonPolicy::default())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(payloads.len(), 3);
+        assert_eq!(payloads[0].as_ref().unwrap(), &Payload { id: 1 });
+        assert!(
+            matches!(&payloads[1], Err(EventError::JsonError(error)) if error.data() == "nope")
+        );
+        assert_eq!(payloads[2].as_ref().unwrap(), &Payload { id: 2 });
+    }
+
+    #[tokio::test]
+    async fn skip_invalid_json() {
+        let events = tokio_stream::iter(vec![Ok(event("nope")), Ok(event(r#"{"id":2}"#))]);
+        let payloads = JsonEvents::<_, Payload>::new(events, InvalidJsonPolicy::Skip)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].as_ref().unwrap(), &Payload { id: 2 });
+    }
+}
@@ -0,0 +1,284 @@
+//! Measurement of the latency of event streams.
+//!
+//! A [`Latencies`] stream forwards the events of the stream it wraps, measuring:
+//! - the gap between consecutive events;
+//! - the delivery time of each event, from its dispatch by the parser, right after its last line
+//!   is read, to its hand-off to the consumer.
+//!
+//! The delivery time covers everything in between, e.g. the channel of
+//! [`EventStream::into_channel`], so the adapter goes last, right before the consumer. It's only
+//! measured for the events stamped with their reception time, see
+//! [`EventSourceConfig::received_at`](crate::config::EventSourceConfig::received_at).
+//!
+//! The percentiles of the last measures are read from a [`LatencyMonitor`], which can be kept
+//! aside, e.g. by a health endpoint.
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::{EventSource, config::EventSourceConfig};
+//!
+//! # async fn run() {
+//! let mut events = reqwest::get("https://sse.test-free.online/api/story")
+//!     .await.unwrap()
+//!     .events_with(EventSourceConfig::new().received_at(true))
+//!     .await.unwrap()
+//!     .latencies(1024);
+//! let monitor = events.monitor();
+//!
+//! while let Some(Ok(_)) = events.next().await {
+//!     println!("{:?}", monitor.stats().delivery);
+//! }
+//! # }
+//! ```
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll, ready},
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures_core::Stream;
+
+use crate::{Event, error::EventError, stream::EventStream};
+
+/// Percentiles of a series of measures.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Percentiles {
+    /// Median.
+    pub p50: Duration,
+    /// 90th percentile.
+    pub p90: Duration,
+    /// 99th percentile.
+    pub p99: Duration,
+    /// Maximum.
+    pub max: Duration,
+}
+
+/// Snapshot of the latencies measured by a [`Latencies`] stream, over the last measures.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct LatencyStats {
+    /// Gap between consecutive events, if at least two events were received.
+    pub gap: Option<Percentiles>,
+    /// Time from the dispatch of the events by the parser to their hand-off to the consumer, if
+    /// an event stamped with its reception time was received.
+    pub delivery: Option<Percentiles>,
+}
+
+/// Last measures of a series, up to the size of the window.
+#[derive(Debug)]
+struct Window {
+    size: usize,
+    measures: VecDeque<Duration>,
+}
+
+impl Window {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            measures: VecDeque::with_capacity(size),
+        }
+    }
+
+    /// Records a measure, forgetting the oldest one if the window is full.
+    fn push(&mut self, measure: Duration) {
+        if self.measures.len() == self.size {
+            self.measures.pop_front();
+        }
+        self.measures.push_back(measure);
+    }
+
+    /// Returns the percentiles of the measures, with the nearest-rank method.
+    fn percentiles(&self) -> Option<Percentiles> {
+        if self.measures.is_empty() {
+            return None;
+        }
+        let mut measures = self.measures.iter().copied().collect::<Vec<_>>();
+        measures.sort_unstable();
+        let rank = |percent: usize| measures[(measures.len() * percent).div_ceil(100) - 1];
+        Some(Percentiles {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: rank(100),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Measures {
+    gap: Window,
+    delivery: Window,
+}
+
+/// Handle reading the latencies measured by a [`Latencies`] stream.
+///
+/// Clones share the same measures.
+#[derive(Debug, Clone)]
+pub struct LatencyMonitor(Arc<Mutex<Measures>>);
+
+impl LatencyMonitor {
+    /// Returns the percentiles of the last measures.
+    #[must_use]
+    pub fn stats(&self) -> LatencyStats {
+        let measures = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        LatencyStats {
+            gap: measures.gap.percentiles(),
+            delivery: measures.delivery.percentiles(),
+        }
+    }
+}
+
+/// Stream measuring the latency of the events of the wrapped stream, see the
+/// [module documentation](self).
+///
+/// Errors of the wrapped stream are forwarded as is, and aren't measured.
+#[derive(Debug)]
+pub struct Latencies<S> {
+    stream: S,
+    /// Time the last event was handed off.
+    last: Option<Instant>,
+    monitor: LatencyMonitor,
+}
+
+impl<S> Latencies<S> {
+    /// Wraps the stream, keeping the last `window` measures of each series.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is `0`.
+    #[must_use]
+    pub fn new(stream: S, window: usize) -> Self {
+        assert!(window > 0, "latency window must not be empty");
+        Self {
+            stream,
+            last: None,
+            monitor: LatencyMonitor(Arc::new(Mutex::new(Measures {
+                gap: Window::new(window),
+                delivery: Window::new(window),
+            }))),
+        }
+    }
+
+    /// Returns a [`LatencyMonitor`] of the stream.
+    #[must_use]
+    pub fn monitor(&self) -> LatencyMonitor {
+        self.monitor.clone()
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> Stream for Latencies<S>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+{
+    type Item = Result<Event, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(Pin::new(&mut self.stream).poll_next(cx));
+        if let Some(Ok(event)) = &item {
+            let now = Instant::now();
+            let mut measures = self
+                .monitor
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(last) = self.last {
+                measures.gap.push(now - last);
+            }
+            if let Some(received_at) = event.received_at {
+                let delivery = SystemTime::now()
+                    .duration_since(received_at)
+                    .unwrap_or_default();
+                measures.delivery.push(delivery);
+            }
+            drop(measures);
+            self.last = Some(now);
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl EventStream {
+    /// Measures the latency of the events, see [`Latencies`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is `0`.
+    #[must_use]
+    pub fn latencies(self, window: usize) -> Latencies<Self> {
+        Latencies::new(self, window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::event;
+
+    #[test]
+    fn compute_nearest_rank_percentiles() {
+        let mut window = Window::new(100);
+        assert_eq!(window.percentiles(), None);
+
+        for millis in (1..=200).rev() {
+            window.push(Duration::from_millis(millis));
+        }
+        // only the last 100 measures are kept
+        assert_eq!(
+            window.percentiles(),
+            Some(Percentiles {
+                p50: Duration::from_millis(50),
+                p90: Duration::from_millis(90),
+                p99: Duration::from_millis(99),
+                max: Duration::from_millis(100),
+            })
+        );
+
+        let mut window = Window::new(10);
+        window.push(Duration::from_millis(7));
+        let single = Duration::from_millis(7);
+        assert_eq!(
+            window.percentiles(),
+            Some(Percentiles {
+                p50: single,
+                p90: single,
+                p99: single,
+                max: single,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn measure_gaps_and_delivery() {
+        let received_at = SystemTime::now() - Duration::from_secs(1);
+        let events = tokio_stream::iter(vec![
+            Ok(Event {
+                received_at: Some(received_at),
+                ..event("data")
+            }),
+            Err(EventError::StaleConnection),
+            Ok(event("data")),
+        ])
+        .throttle(Duration::from_millis(10));
+        let mut events = Latencies::new(Box::pin(events), 10);
+        let monitor = events.monitor();
+
+        events.next().await;
+        let stats = monitor.stats();
+        assert_eq!(stats.gap, None);
+        assert!(stats.delivery.unwrap().max >= Duration::from_secs(1));
+
+        while events.next().await.is_some() {}
+        let stats = monitor.stats();
+        assert!(stats.gap.unwrap().max >= Duration::from_millis(20));
+        assert_eq!(stats.delivery.unwrap().p50, stats.delivery.unwrap().max);
+    }
+}
@@ -28,24 +28,114 @@
 //!     }
 //! }
 //! ```
+//!
+//! ## Stream interoperability
+//!
+//! The returned streams implement [`futures_core::Stream`], re-exported as [`Stream`], so they
+//! work with the combinators of either `tokio_stream::StreamExt` or `futures::StreamExt`, without
+//! depending on `tokio_stream`.
+//!
+//! ## Buffering and backpressure
+//!
+//! The streams are pull-based: the response body is only read while the stream is polled, and
+//! only until the next item is parsed. Besides the event being parsed, nothing is buffered but the
+//! chunk being read, and the buffer set with
+//! [`EventSourceConfig::read_buffer_capacity`](config::EventSourceConfig::read_buffer_capacity).
+//! While the consumer doesn't poll, the server-sent data is left to the transport, and TCP flow
+//! control eventually slows the server down.
+//!
+//! Each line parsed consumes a unit of the Tokio task budget, so a burst of buffered events, e.g.
+//! a backlog flushed at once, makes the task yield to the runtime periodically instead of
+//! starving the other tasks.
+//!
+//! The APIs driving the stream from a task bound what they read ahead:
+//! - [`EventStream::into_channel`](stream::EventStream::into_channel) buffers at most `capacity`
+//!   items in the channel, plus the one waiting for room. Once full, it stops reading, so the
+//!   backpressure reaches the transport.
+//! - [`Broadcast`](stream::Broadcast) never waits for the receivers: it buffers at most `capacity`
+//!   items, and receivers falling further behind skip the oldest ones.
+//!
+//! ## Tracing
+//!
+//! With the `tracing` feature, the streams emit [`tracing`](https://docs.rs/tracing) events:
+//! - the connections, at the `debug` level, in an `event_stream` span with the URL;
+//! - the reconnection attempts, in a `reconnecting_event_source` span, at the `info` level, and
+//!   their failures at the `warn` level;
+//! - the parse warnings, at the `warn` level, see
+//!   [`EventSourceConfig::on_warning`](config::EventSourceConfig::on_warning);
+//! - the dispatched events, at the `trace` level, with their type, data length, and ID, and the
+//!   stream errors at the `debug` level.
+pub mod backfill;
+#[cfg(feature = "base64")]
+pub mod binary;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod breaker;
+pub mod config;
+#[cfg(feature = "json")]
+pub mod delta;
+pub mod done;
 pub mod error;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod latency;
+pub mod manager;
+#[cfg(feature = "mercure")]
+pub mod mercure;
+pub mod pause;
+pub mod reconnect;
+pub mod router;
+pub mod stats;
+pub mod stream;
 
-use std::{pin::Pin, time::Duration};
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    io,
+    ops::ControlFlow,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, ready},
+    time::{Duration, SystemTime},
+};
 
-use async_stream::try_stream;
+use bytes::Bytes;
 use reqwest::{
-    Response, StatusCode,
-    header::{CONTENT_TYPE, HeaderValue},
+    Client, IntoUrl, RequestBuilder, Response, StatusCode,
+    header::{
+        ACCEPT, ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue,
+    },
+};
+use tokio::{io::AsyncBufRead, task::JoinHandle, time::Sleep};
+use tokio_stream::StreamExt;
+use tokio_util::{
+    io::StreamReader,
+    sync::{CancellationToken, WaitForCancellationFutureOwned},
+};
+
+use crate::{
+    config::{
+        EventSourceConfig, Field, IdleActivity, InvalidUtf8Policy, LongLinePolicy, ParseMode,
+        TruncatedEventPolicy, Validator,
+    },
+    error::{EventError, EventSourceError, ParseWarning},
+    reconnect::ReconnectingEventSource,
+    stream::{EventStream, Metadata, SharedState},
 };
-use tokio::io::AsyncBufReadExt;
-use tokio_stream::{Stream, StreamExt};
-use tokio_util::io::StreamReader;
 
-use crate::error::{EventError, EventSourceError};
+/// The trait implemented by all the streams of this crate, from `futures-core`.
+pub use futures_core::Stream;
+#[cfg(feature = "derive")]
+pub use reqwest_sse_derive::SseEvent;
 
 /// `text/event-stream` MIME type as [`HeaderValue`].
 pub static MIME_EVENT_STREAM: HeaderValue = HeaderValue::from_static("text/event-stream");
 
+/// `Last-Event-ID` header name as [`HeaderName`].
+pub static LAST_EVENT_ID: HeaderName = HeaderName::from_static("last-event-id");
+
 /// Internal buffer used to accumulate lines of an SSE (Server-Sent Events) stream.
 ///
 /// A single [`EventBuffer`] can be used to process the whole stream. [`set_event_type`] and [`push_data`]
@@ -56,8 +146,26 @@ struct EventBuffer {
     data: String,
     last_event_id: Option<String>,
     retry: Option<Duration>,
+    extra: Vec<(String, String)>,
+    /// Lines of the current event, only when captured.
+    raw: Option<String>,
+    has_id: bool,
+    /// Whether the current type of the event isn't accepted, so its data isn't buffered.
+    filtered: bool,
+    /// Allocation of the last event ID of a recycled event, reused by the next event.
+    spare_id: Option<String>,
+    /// Size of the data of the last event, reserved for the next one once the data moved into it.
+    data_capacity: usize,
+    /// Data of the current event, in [`Mode::Bytes`], where it isn't validated as UTF-8.
+    bytes: Vec<u8>,
+    /// Event types already seen, shared by the events of the same type.
+    event_types: Vec<Arc<str>>,
 }
 
+/// Maximum number of event types interned by an [`EventBuffer`], beyond which each event
+/// allocates its own type, so that a stream of ever-changing types doesn't grow the buffer.
+const MAX_INTERNED_EVENT_TYPES: usize = 32;
+
 impl EventBuffer {
     /// Creates fresh new [`EventBuffer`].
     #[allow(clippy::new_without_default)]
@@ -67,9 +175,77 @@ impl EventBuffer {
             data: String::new(),
             last_event_id: None,
             retry: None,
+            extra: Vec::new(),
+            raw: None,
+            has_id: false,
+            filtered: false,
+            spare_id: None,
+            data_capacity: 0,
+            bytes: Vec::new(),
+            event_types: vec![Arc::from("message")],
         }
     }
 
+    /// Whether data of the current event is buffered.
+    fn has_data(&self) -> bool {
+        !self.data.is_empty() || !self.bytes.is_empty()
+    }
+
+    /// Returns the type of the current event, `message` by default, sharing the allocation of
+    /// the previous events of the same type.
+    fn intern_event_type(&mut self) -> Arc<str> {
+        let event_type = if self.event_type.is_empty() {
+            "message"
+        } else {
+            self.event_type.as_str()
+        };
+        if let Some(interned) = self
+            .event_types
+            .iter()
+            .find(|interned| &***interned == event_type)
+        {
+            return Arc::clone(interned);
+        }
+        let event_type = Arc::<str>::from(event_type);
+        if self.event_types.len() < MAX_INTERNED_EVENT_TYPES {
+            self.event_types.push(Arc::clone(&event_type));
+        }
+        event_type
+    }
+
+    /// Produces a [`BytesEvent`] with the data accumulated as bytes, if current state allow it.
+    ///
+    /// Reset the internal state to process further data.
+    fn produce_bytes_event(&mut self) -> Option<BytesEvent> {
+        let event = if self.bytes.is_empty() {
+            None
+        } else {
+            // strip the line feed following the last `data` line
+            let mut data = Bytes::from(std::mem::take(&mut self.bytes));
+            data.truncate(data.len() - 1);
+            Some(BytesEvent {
+                event_type: self.intern_event_type(),
+                data,
+                last_event_id: self.last_event_id.clone(),
+                retry: self.retry,
+            })
+        };
+
+        self.event_type.clear();
+        self.extra.clear();
+        self.raw = None;
+        self.has_id = false;
+        self.filtered = false;
+
+        event
+    }
+
+    /// Extends the data accumulated as bytes with given data, followed by a line feed.
+    fn push_bytes(&mut self, data: &[u8]) {
+        self.bytes.extend_from_slice(data);
+        self.bytes.push(b'\n');
+    }
+
     /// Produces a [`Event`], if current state allow it.
     ///
     /// Reset the internal state to process further data.
@@ -77,45 +253,234 @@ impl EventBuffer {
         let event = if self.data.is_empty() {
             None
         } else {
+            // the buffered data moves into the event, without the line feed following the last
+            // `data` line
+            let mut data = std::mem::take(&mut self.data);
+            self.data_capacity = data.len();
+            data.pop();
             Some(Event {
-                event_type: if self.event_type.is_empty() {
-                    "message".to_string()
-                } else {
-                    self.event_type.clone()
-                },
-                data: self.data.to_string(),
-                last_event_id: self.last_event_id.clone(),
+                event_type: self.intern_event_type(),
+                data,
+                last_event_id: self.last_event_id.as_deref().map(|id| {
+                    let mut last_event_id = self.spare_id.take().unwrap_or_default();
+                    last_event_id.clear();
+                    last_event_id.push_str(id);
+                    last_event_id
+                }),
                 retry: self.retry,
+                extra: std::mem::take(&mut self.extra),
+                raw: self.raw.take(),
+                received_at: None,
+                sequence: None,
             })
         };
 
         self.event_type.clear();
+        self.extra.clear();
+        self.raw = None;
         self.data.clear();
+        self.has_id = false;
+        self.filtered = false;
 
         event
     }
 
+    /// Takes back the allocations of a produced event, so the next one reuses them.
+    fn recycle(&mut self, event: Event) {
+        if self.data.capacity() == 0 {
+            self.data = event.data;
+            self.data.clear();
+        }
+        if let Some(last_event_id) = event.last_event_id {
+            self.spare_id.get_or_insert(last_event_id);
+        }
+        if self.extra.capacity() == 0 {
+            self.extra = event.extra;
+            self.extra.clear();
+        }
+    }
+
     /// Set the [`Event`]'s type. Overide previous value.
     fn set_event_type(&mut self, event_type: &str) {
         self.event_type.clear();
         self.event_type.push_str(event_type);
     }
 
-    /// Extends internal data with given data.
+    /// Extends internal data with given data, followed by a line feed.
+    ///
+    /// An empty `data` field still produces an event, with empty data.
     fn push_data(&mut self, data: &str) {
-        if !self.data.is_empty() {
-            self.data.push('\n');
+        if self.data.capacity() == 0 {
+            self.data.reserve(self.data_capacity);
         }
         self.data.push_str(data);
+        self.data.push('\n');
     }
 
-    fn set_id(&mut self, id: &str) {
-        self.last_event_id = Some(id.to_string());
+    /// Returns the size of the produced data in bytes, once extended with given data.
+    fn data_size_with(&self, data: &str) -> usize {
+        self.data.len() + data.len()
+    }
+
+    /// Set the last event ID, carried by all following events until changed.
+    ///
+    /// An empty ID resets the last event ID. An ID containing a NULL character is ignored, as
+    /// required by the specification, so it can't be sent back in the `Last-Event-ID` header.
+    /// Returns whether the ID is accepted.
+    fn set_id(&mut self, id: &str) -> bool {
+        if id.contains('\0') {
+            return false;
+        }
+        self.last_event_id = if id.is_empty() {
+            None
+        } else {
+            Some(id.to_string())
+        };
+        self.has_id = true;
+        true
     }
 
     fn set_retry(&mut self, retry: Duration) {
         self.retry = Some(retry);
     }
+
+    /// Captures a line of the current event.
+    fn push_raw(&mut self, line: &str) {
+        let raw = self.raw.get_or_insert_default();
+        raw.push_str(line);
+        raw.push('\n');
+    }
+
+    /// Adds a field unknown by the parser.
+    fn push_extra(&mut self, field: &str, value: &str) {
+        self.extra.push((field.to_string(), value.to_string()));
+    }
+}
+
+/// UTF-8 encoded byte order mark, ignored at the start of the stream.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Outcome of [`LineReader::poll_read_line`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ReadLine {
+    /// A line has been read, available with [`LineReader::line`].
+    Line,
+    /// The line exceeds the maximum length: nothing is read, and the rest of the line is skipped
+    /// by the next read.
+    TooLong,
+    /// The end of stream is reached without any line to read.
+    End,
+}
+
+/// Reader of the response body, type-erased so the [`EventStream`] can give it back.
+pub(crate) type BodyReader = Box<dyn AsyncBufRead + Send + Unpin>;
+
+/// Reader splitting the stream into lines, terminated by either `\r\n`, `\n`, or `\r`.
+///
+/// Lines are read as raw bytes, and only decoded once complete, so code points split across chunks
+/// are reassembled. A leading byte order mark is stripped from the first line.
+#[allow(clippy::struct_excessive_bools)]
+struct LineReader<R> {
+    reader: R,
+    /// The line being read, or the last one read if complete.
+    line: Vec<u8>,
+    /// Whether the line is complete, so it's cleared by the next read.
+    complete: bool,
+    /// Whether the previous line ended with `\r`, so a leading `\n` completes its terminator.
+    skip_lf: bool,
+    /// Whether the first line has been read, and its byte order mark stripped.
+    started: bool,
+    /// Maximum length of a line, terminator excluded.
+    max_length: Option<usize>,
+    /// Whether the rest of a too long line must be skipped.
+    skip_line: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> LineReader<R> {
+    fn new(reader: R, max_length: Option<usize>) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+            complete: false,
+            skip_lf: false,
+            started: false,
+            max_length,
+            skip_line: false,
+        }
+    }
+
+    /// Returns the last line read, without its terminator.
+    fn line(&self) -> &[u8] {
+        &self.line
+    }
+
+    /// Reads the next line, made available with [`LineReader::line`].
+    ///
+    /// The bytes already read are kept while pending. A last line without terminator is still
+    /// returned.
+    fn poll_read_line(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<ReadLine>> {
+        if std::mem::take(&mut self.complete) {
+            self.line.clear();
+        }
+        loop {
+            let available = ready!(Pin::new(&mut self.reader).poll_fill_buf(cx))?;
+            if available.is_empty() {
+                self.skip_line = false;
+                self.strip_bom();
+                self.complete = true;
+                return Poll::Ready(Ok(if self.line.is_empty() {
+                    ReadLine::End
+                } else {
+                    ReadLine::Line
+                }));
+            }
+
+            let start = usize::from(self.skip_lf && available[0] == b'\n');
+            let terminator =
+                memchr::memchr2(b'\n', b'\r', &available[start..]).map(|position| start + position);
+            self.skip_lf = terminator.is_some_and(|end| available[end] == b'\r');
+            let end = terminator.unwrap_or(available.len());
+            if !self.skip_line {
+                self.line.extend_from_slice(&available[start..end]);
+            }
+            Pin::new(&mut self.reader).consume(terminator.map_or(end, |end| end + 1));
+
+            if self.skip_line {
+                self.skip_line = terminator.is_none();
+            } else if self.max_length.is_some_and(|max| self.line.len() > max) {
+                self.line.clear();
+                self.complete = true;
+                self.started = true;
+                self.skip_line = terminator.is_none();
+                return Poll::Ready(Ok(ReadLine::TooLong));
+            } else if terminator.is_some() {
+                self.strip_bom();
+                self.complete = true;
+                return Poll::Ready(Ok(ReadLine::Line));
+            }
+        }
+    }
+
+    /// Returns the underlying reader, with the bytes of the line being read, and whether a leading
+    /// `\n` must be skipped as the end of the last line terminator.
+    fn into_parts(self) -> (R, Vec<u8>, bool) {
+        let pending = if self.complete || self.skip_line {
+            Vec::new()
+        } else {
+            self.line
+        };
+        (self.reader, pending, self.skip_lf)
+    }
+
+    /// Strips the byte order mark of the line if it's the first one.
+    fn strip_bom(&mut self) {
+        if !self.started {
+            self.started = true;
+            if self.line.starts_with(BOM) {
+                self.line.drain(..BOM.len());
+            }
+        }
+    }
 }
 
 /// Parse line to split field name and value, applying proper trimming.
@@ -125,14 +490,183 @@ fn parse_line(line: &str) -> (&str, &str) {
     (field, value)
 }
 
+/// Reason of a `retry` field value being ignored.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum InvalidRetry {
+    /// The value isn't made of ASCII digits only.
+    NotDigits,
+    /// The value is too large to be represented.
+    Overflow,
+}
+
+/// Parses the value of a `retry` field as a number of milliseconds.
+fn parse_retry(value: &str, parse_mode: ParseMode) -> Result<Duration, InvalidRetry> {
+    let digits = match parse_mode {
+        ParseMode::Strict => value,
+        ParseMode::Lenient => {
+            let value = value.trim();
+            let end = value
+                .find(|character: char| !character.is_ascii_digit())
+                .unwrap_or(value.len());
+            &value[..end]
+        }
+    };
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(InvalidRetry::NotDigits);
+    }
+    digits
+        .parse()
+        .map(Duration::from_millis)
+        .map_err(|_| InvalidRetry::Overflow)
+}
+
+/// Reports a [`ParseWarning`] to the configured handler, and to `tracing` if enabled.
+fn warn(config: &EventSourceConfig, warning: ParseWarning) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(%warning, "malformed event stream");
+    if let Some(handler) = &config.on_warning {
+        handler.call(warning);
+    }
+}
+
 /// Server-Sent Event representation.
+///
+/// With the `serde` feature, it implements `Serialize` and `Deserialize`, with the reconnection
+/// time as a number of milliseconds.
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event {
     /// A string identifying the type of event described.
-    pub event_type: String,
+    ///
+    /// Types are interned by the parser, so that events of the same type share one allocation.
+    pub event_type: Arc<str>,
     /// The data field for the message.
     pub data: String,
     /// Last event ID value.
+    ///
+    /// As per the specification, it's the ID of the last event sent with an `id` field, not
+    /// necessarily this one. It persists until the server sends a new one, or resets it with an
+    /// empty `id` field.
+    pub last_event_id: Option<String>,
+    /// Reconnection time.
+    #[cfg_attr(feature = "serde", serde(with = "retry_millis"))]
+    pub retry: Option<Duration>,
+    /// Fields unknown by the parser, as name and value pairs in order of appearance.
+    ///
+    /// Only collected when enabled with [`EventSourceConfig::unknown_fields`], empty otherwise.
+    pub extra: Vec<(String, String)>,
+    /// Lines of the event as sent by the server, comments included, each followed by a line feed
+    /// whatever its original terminator.
+    ///
+    /// Only captured when enabled with [`EventSourceConfig::raw`], `None` otherwise.
+    pub raw: Option<String>,
+    /// Time of the event dispatch, once its last line is read.
+    ///
+    /// Only captured when enabled with [`EventSourceConfig::received_at`], `None` otherwise.
+    pub received_at: Option<SystemTime>,
+    /// Number of the event on its connection, starting from `0`, without gaps: events skipped by
+    /// the parser aren't numbered.
+    ///
+    /// Only assigned when enabled with [`EventSourceConfig::sequence`], `None` otherwise.
+    pub sequence: Option<u64>,
+}
+
+#[cfg(feature = "base64")]
+impl Event {
+    /// Decodes the data of the event as standard base64, padded or not.
+    ///
+    /// Line feeds are ignored, so that payloads split across several `data` lines decode whole.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EventError::Base64Error`] if the data isn't valid base64.
+    pub fn decode_base64(&self) -> Result<Bytes, EventError> {
+        use base64::{
+            Engine,
+            alphabet::STANDARD,
+            engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+        };
+
+        const ENGINE: GeneralPurpose = GeneralPurpose::new(
+            &STANDARD,
+            GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+        );
+
+        let data = self.data.replace('\n', "");
+        ENGINE
+            .decode(data)
+            .map(Bytes::from)
+            .map_err(EventError::Base64Error)
+    }
+}
+
+/// (De)serialization of [`Event::retry`] as a number of milliseconds, as sent by the server.
+#[cfg(feature = "serde")]
+mod retry_millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[allow(clippy::ref_option)]
+    pub(crate) fn serialize<S: Serializer>(
+        retry: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        retry
+            .map(|retry| u64::try_from(retry.as_millis()).unwrap_or(u64::MAX))
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+    }
+}
+
+/// Item of a stream including comments, see [`EventSource::events_with_comments`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EventOrComment {
+    /// A dispatched event.
+    Event(Event),
+    /// A comment line, without its leading colon and space, e.g. `keep-alive` for `: keep-alive`.
+    Comment(String),
+}
+
+/// Item of a stream exposing the protocol, see [`EventSource::sse_items`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SseItem {
+    /// A dispatched event.
+    Event(Event),
+    /// A comment line, without its leading colon and space.
+    Comment(String),
+    /// A reconnection time sent with a `retry` field, as soon as it's read, even if the event it
+    /// belongs to is never dispatched.
+    Retry(Duration),
+}
+
+/// Item of a stream of fields, see [`EventSource::fields`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FieldOrDispatch {
+    /// A field, as sent by the server, e.g. `data` and `value` for `data: value`.
+    Field {
+        /// The name of the field, matched against neither the known ones nor the aliases.
+        name: String,
+        /// The value of the field, without its leading space.
+        value: String,
+    },
+    /// A blank line, ending the current event for standard parsers.
+    Dispatch,
+}
+
+/// Server-Sent Event with its data as opaque bytes, see [`EventSource::bytes_events`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BytesEvent {
+    /// A string identifying the type of event described, see [`Event::event_type`].
+    pub event_type: Arc<str>,
+    /// The data field for the message, as sent by the server, not validated as UTF-8.
+    pub data: Bytes,
+    /// Last event ID value, see [`Event::last_event_id`].
     pub last_event_id: Option<String>,
     /// Reconnection time.
     pub retry: Option<Duration>,
@@ -146,85 +680,1240 @@ pub trait EventSource {
     /// # Errors
     ///
     /// Returns an [`EventSourceError`] if:
-    /// - The response status is not `200 OK`
+    /// - The response status is not `200 OK`, or not accepted by
+    ///   [`EventSourceConfig::accept_status`]
     /// - The `Content-Type` header is missing or not `text/event-stream`
+    /// - The response is rejected by [`EventSourceConfig::validate`]
+    ///
+    /// The stream yields an [`EventError`] when error occure on event reading. A `204 No Content`
+    /// response, telling the client not to reconnect, produces an empty stream.
+    fn events(self) -> impl Future<Output = Result<EventStream, EventSourceError>> + Send;
+
+    /// Converts the [`Response`] into a stream of Server-Sent Events, like [`EventSource::events`],
+    /// with the given [`EventSourceConfig`].
     ///
-    /// The stream yields an [`EventError`] when error occure on event reading.
-    fn events(
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`].
+    fn events_with(
         self,
-    ) -> impl Future<
-        Output = Result<Pin<Box<impl Stream<Item = Result<Event, EventError>>>>, EventSourceError>,
-    > + Send;
+        config: EventSourceConfig,
+    ) -> impl Future<Output = Result<EventStream, EventSourceError>> + Send;
+
+    /// Converts the [`Response`] into a stream of Server-Sent Events, like
+    /// [`EventSource::events_with`], that also yields the comment lines.
+    ///
+    /// Comments are commonly sent as keep-alive signals, so observing them tells the connection is
+    /// still alive even without events.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`].
+    fn events_with_comments(
+        self,
+        config: EventSourceConfig,
+    ) -> impl Future<Output = Result<EventStream<EventOrComment>, EventSourceError>> + Send;
+
+    /// Converts the [`Response`] into a stream of its lines, before field parsing, with the given
+    /// [`EventSourceConfig`], e.g. to debug a server or build a custom parser.
+    ///
+    /// Lines are split on any terminator, which is stripped, and decoded according to
+    /// [`EventSourceConfig::invalid_utf8`]. Blank lines, ending events, are yielded as empty
+    /// strings. The response is validated the same way as for [`EventSource::events`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`].
+    fn sse_lines(
+        self,
+        config: EventSourceConfig,
+    ) -> impl Future<Output = Result<EventStream<String>, EventSourceError>> + Send;
+
+    /// Converts the [`Response`] into a stream of its fields and dispatch boundaries, with the
+    /// given [`EventSourceConfig`], e.g. to implement non-standard dispatch rules.
+    ///
+    /// Lines are read as for [`EventSource::sse_lines`], then split into field names and values.
+    /// Comments are skipped, and each blank line yields a [`FieldOrDispatch::Dispatch`], even if
+    /// no field precedes it.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`].
+    fn fields(
+        self,
+        config: EventSourceConfig,
+    ) -> impl Future<Output = Result<EventStream<FieldOrDispatch>, EventSourceError>> + Send;
+
+    /// Converts the [`Response`] into a stream of [`SseItem`]s, with the given
+    /// [`EventSourceConfig`]: the events, along with the comments and the reconnection times, e.g.
+    /// for proxies and monitoring tools.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`].
+    fn sse_items(
+        self,
+        config: EventSourceConfig,
+    ) -> impl Future<Output = Result<EventStream<SseItem>, EventSourceError>> + Send;
+
+    /// Converts the [`Response`] into a stream of [`BytesEvent`]s, with the given
+    /// [`EventSourceConfig`], e.g. for proxies forwarding opaque data verbatim.
+    ///
+    /// The `data` lines are matched and accumulated as raw bytes, so they aren't validated as
+    /// UTF-8: the [`InvalidUtf8Policy`] only applies to the other lines.
+    /// Comments are skipped, raw lines aren't captured, and events aren't stamped. An event
+    /// truncated by the end of the stream is reported with its data decoded lossily.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`].
+    fn bytes_events(
+        self,
+        config: EventSourceConfig,
+    ) -> impl Future<Output = Result<EventStream<BytesEvent>, EventSourceError>> + Send;
+
+    /// Converts the [`Response`] into a stream of Server-Sent Events, like [`EventSource::events`],
+    /// that ends when the given [`CancellationToken`] is cancelled.
+    ///
+    /// Shorthand for [`EventSourceConfig::cancellation_token`], to combine with other options.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`].
+    fn events_until_cancelled(
+        self,
+        token: CancellationToken,
+    ) -> impl Future<Output = Result<EventStream, EventSourceError>> + Send;
 }
 
 impl EventSource for Response {
-    async fn events(
+    async fn events(self) -> Result<EventStream, EventSourceError> {
+        into_event_stream(self, None, EventSourceConfig::default()).await
+    }
+
+    async fn events_with(self, config: EventSourceConfig) -> Result<EventStream, EventSourceError> {
+        into_event_stream(self, None, config).await
+    }
+
+    async fn events_with_comments(
         self,
-    ) -> Result<Pin<Box<impl Stream<Item = Result<Event, EventError>>>>, EventSourceError> {
-        let status = self.status();
-        if status != StatusCode::OK {
-            return Err(EventSourceError::BadStatus(status));
+        config: EventSourceConfig,
+    ) -> Result<EventStream<EventOrComment>, EventSourceError> {
+        let metadata = Metadata::of(&self);
+        let state = SharedState::default();
+        let parser = parse_response(self, state.clone(), config, Mode::Comments).await?;
+        Ok(EventStream::new(
+            metadata,
+            state,
+            parser,
+            |item| match item {
+                Parsed::Event(event, _) => Some(EventOrComment::Event(event)),
+                Parsed::Comment(comment) => Some(EventOrComment::Comment(comment)),
+                _ => None,
+            },
+        ))
+    }
+
+    async fn sse_lines(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<String>, EventSourceError> {
+        let metadata = Metadata::of(&self);
+        let state = SharedState::default();
+        let parser = parse_response(self, state.clone(), config, Mode::Lines).await?;
+        Ok(EventStream::new(
+            metadata,
+            state,
+            parser,
+            |item| match item {
+                Parsed::Line(line) => Some(line),
+                _ => None,
+            },
+        ))
+    }
+
+    async fn fields(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<FieldOrDispatch>, EventSourceError> {
+        let metadata = Metadata::of(&self);
+        let state = SharedState::default();
+        let parser = parse_response(self, state.clone(), config, Mode::Fields).await?;
+        Ok(EventStream::new(
+            metadata,
+            state,
+            parser,
+            |item| match item {
+                Parsed::Field(name, value) => Some(FieldOrDispatch::Field { name, value }),
+                Parsed::Dispatch => Some(FieldOrDispatch::Dispatch),
+                _ => None,
+            },
+        ))
+    }
+
+    async fn sse_items(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<SseItem>, EventSourceError> {
+        let metadata = Metadata::of(&self);
+        let state = SharedState::default();
+        let parser = parse_response(self, state.clone(), config, Mode::Items).await?;
+        Ok(EventStream::new(
+            metadata,
+            state,
+            parser,
+            |item| match item {
+                Parsed::Event(event, _) => Some(SseItem::Event(event)),
+                Parsed::Comment(comment) => Some(SseItem::Comment(comment)),
+                Parsed::Retry(retry) => Some(SseItem::Retry(retry)),
+                _ => None,
+            },
+        ))
+    }
+
+    async fn bytes_events(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<BytesEvent>, EventSourceError> {
+        let metadata = Metadata::of(&self);
+        let state = SharedState::default();
+        let parser = parse_response(self, state.clone(), config, Mode::Bytes).await?;
+        Ok(EventStream::new(
+            metadata,
+            state,
+            parser,
+            |item| match item {
+                Parsed::BytesEvent(event) => Some(event),
+                _ => None,
+            },
+        ))
+    }
+
+    async fn events_until_cancelled(
+        self,
+        token: CancellationToken,
+    ) -> Result<EventStream, EventSourceError> {
+        into_event_stream(
+            self,
+            None,
+            EventSourceConfig::new().cancellation_token(token),
+        )
+        .await
+    }
+}
+
+/// Allows chaining the conversion right after sending the request, e.g.
+/// `reqwest::get(url).await.events().await`, the request error being reported as
+/// [`EventSourceError::RequestError`].
+impl EventSource for Result<Response, reqwest::Error> {
+    async fn events(self) -> Result<EventStream, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?.events().await
+    }
+
+    async fn events_with(self, config: EventSourceConfig) -> Result<EventStream, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?
+            .events_with(config)
+            .await
+    }
+
+    async fn events_with_comments(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<EventOrComment>, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?
+            .events_with_comments(config)
+            .await
+    }
+
+    async fn sse_lines(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<String>, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?
+            .sse_lines(config)
+            .await
+    }
+
+    async fn fields(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<FieldOrDispatch>, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?
+            .fields(config)
+            .await
+    }
+
+    async fn sse_items(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<SseItem>, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?
+            .sse_items(config)
+            .await
+    }
+
+    async fn bytes_events(
+        self,
+        config: EventSourceConfig,
+    ) -> Result<EventStream<BytesEvent>, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?
+            .bytes_events(config)
+            .await
+    }
+
+    async fn events_until_cancelled(
+        self,
+        token: CancellationToken,
+    ) -> Result<EventStream, EventSourceError> {
+        self.map_err(EventSourceError::RequestError)?
+            .events_until_cancelled(token)
+            .await
+    }
+}
+
+/// A trait sending a [`RequestBuilder`] and consuming its response as a [`Stream`] of
+/// Server-Sent [`Event`]s, in one call.
+///
+/// The `Accept: text/event-stream` header is set on the request.
+pub trait EventSourceRequest {
+    /// Sends the request and converts the response into a stream of Server-Sent Events, like
+    /// [`EventSource::events`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSource::events`], plus [`EventSourceError::RequestError`] if the request
+    /// fails, and [`EventSourceError::ConnectTimeout`] if the
+    /// [`EventSourceConfig::connect_timeout`] elapses.
+    fn events(self) -> impl Future<Output = Result<EventStream, EventSourceError>> + Send;
+
+    /// Sends the request and converts the response into a stream of Server-Sent Events, like
+    /// [`EventSource::events_with`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`EventSourceRequest::events`].
+    fn events_with(
+        self,
+        config: EventSourceConfig,
+    ) -> impl Future<Output = Result<EventStream, EventSourceError>> + Send;
+
+    /// Returns a [`ReconnectingEventSource`] sending the request on each connection.
+    fn reconnecting(self) -> ReconnectingEventSource;
+
+    /// Sets the headers suited to event streams:
+    /// - `Accept: text/event-stream`,
+    /// - `Cache-Control: no-cache`, so no intermediary serves a stale stream,
+    /// - `Accept-Encoding: identity`, as compressed responses are commonly buffered by servers and
+    ///   proxies, delaying the events.
+    ///
+    /// It also overrides the client's timeout, if any, which would otherwise end the stream once
+    /// elapsed. Use [`EventSourceConfig::stale_timeout`] to detect dead connections instead.
+    fn sse_headers(self) -> RequestBuilder;
+}
+
+impl EventSourceRequest for RequestBuilder {
+    async fn events(self) -> Result<EventStream, EventSourceError> {
+        self.header(ACCEPT, MIME_EVENT_STREAM.clone())
+            .send()
+            .await
+            .events()
+            .await
+    }
+
+    async fn events_with(self, config: EventSourceConfig) -> Result<EventStream, EventSourceError> {
+        let connect_timeout = config.connect_timeout;
+        let connect = async {
+            self.header(ACCEPT, MIME_EVENT_STREAM.clone())
+                .send()
+                .await
+                .events_with(config)
+                .await
+        };
+        match connect_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect)
+                .await
+                .map_err(|_| EventSourceError::ConnectTimeout)?,
+            None => connect.await,
+        }
+    }
+
+    fn reconnecting(self) -> ReconnectingEventSource {
+        ReconnectingEventSource::new(self.header(ACCEPT, MIME_EVENT_STREAM.clone()))
+    }
+
+    fn sse_headers(self) -> RequestBuilder {
+        self.header(ACCEPT, MIME_EVENT_STREAM.clone())
+            .header(CACHE_CONTROL, HeaderValue::from_static("no-cache"))
+            .header(ACCEPT_ENCODING, HeaderValue::from_static("identity"))
+            // far enough to never elapse
+            .timeout(Duration::MAX)
+    }
+}
+
+/// A trait creating requests to event sources from a [`Client`].
+pub trait EventSourceClient {
+    /// Creates a `GET` request to the event source, ready for [`EventSourceRequest`], with
+    /// [`EventSourceRequest::sse_headers`].
+    fn sse<U: IntoUrl>(&self, url: U) -> RequestBuilder;
+}
+
+impl EventSourceClient for Client {
+    fn sse<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.get(url).sse_headers()
+    }
+}
+
+/// Marker of the I/O errors produced by [`Watchdog`].
+#[derive(Debug, Clone, Copy)]
+enum BodyTimeout {
+    /// [`EventSourceConfig::stale_timeout`] elapsed.
+    Stale,
+    /// [`EventSourceConfig::idle_timeout`] elapsed, with [`IdleActivity::Bytes`].
+    Idle,
+}
+
+impl Display for BodyTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyTimeout::Stale => write!(f, "no data received, connection is stale"),
+            BodyTimeout::Idle => write!(f, "no data received, event stream is idle"),
         }
-        let content_type = self.headers().get(CONTENT_TYPE);
-        if content_type != Some(&MIME_EVENT_STREAM) {
-            return Err(EventSourceError::BadContentType(content_type.cloned()));
+    }
+}
+
+impl std::error::Error for BodyTimeout {}
+
+/// Stream forwarding the items of the wrapped stream, failing with a [`BodyTimeout`] I/O error if
+/// none is received within the timeout.
+struct Watchdog<S> {
+    stream: S,
+    timeout: Option<Duration>,
+    error: BodyTimeout,
+    /// Deadline of the current wait, reset when a wait starts, i.e. the wrapped stream is pending.
+    ///
+    /// Created on the first wait with a timeout, so the stream only needs the time driver if
+    /// there is one.
+    deadline: Option<Pin<Box<Sleep>>>,
+    /// Whether the deadline is set for the current wait.
+    waiting: bool,
+    /// Whether the stream has ended.
+    done: bool,
+}
+
+impl<S> Watchdog<S> {
+    fn new(stream: S, timeout: Option<Duration>, error: BodyTimeout) -> Self {
+        Self {
+            stream,
+            timeout,
+            error,
+            deadline: None,
+            waiting: false,
+            done: false,
         }
+    }
+}
 
-        let mut stream = StreamReader::new(
-            self.bytes_stream()
-                .map(|result| result.map_err(std::io::Error::other)),
-        );
+impl<S, T> Stream for Watchdog<S>
+where
+    S: Stream<Item = io::Result<T>> + Unpin,
+{
+    type Item = io::Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(None);
+        }
+        if let Poll::Ready(item) = Pin::new(&mut this.stream).poll_next(cx) {
+            this.waiting = false;
+            this.done = item.is_none();
+            return Poll::Ready(item);
+        }
+        let Some(timeout) = this.timeout else {
+            return Poll::Pending;
+        };
+        let sleep = this
+            .deadline
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        if !this.waiting {
+            this.waiting = true;
+            sleep.as_mut().reset(tokio::time::Instant::now() + timeout);
+        }
+        ready!(sleep.as_mut().poll(cx));
+        this.done = true;
+        Poll::Ready(Some(Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            this.error,
+        ))))
+    }
+}
+
+/// Converts an I/O error of the body reader into an [`EventError`].
+fn into_event_error(error: io::Error) -> EventError {
+    match error
+        .get_ref()
+        .and_then(<dyn std::error::Error + Send + Sync>::downcast_ref::<BodyTimeout>)
+    {
+        Some(BodyTimeout::Stale) => EventError::StaleConnection,
+        Some(BodyTimeout::Idle) => EventError::IdleTimeout,
+        None => EventError::IoError(error),
+    }
+}
+
+/// Spawns a task with the given name, for `tokio-console` when built with `--cfg tokio_unstable`
+/// and the `tracing` feature. With the `tracing` feature, the task also runs in a span with the
+/// name.
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+#[track_caller]
+pub(crate) fn spawn<F>(name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(feature = "tracing")]
+    let future = tracing::Instrument::instrument(future, tracing::debug_span!("task", name));
+    #[cfg(all(tokio_unstable, feature = "tracing"))]
+    return tokio::task::Builder::new()
+        .name(name)
+        .spawn(future)
+        .expect("failed to spawn task");
+    #[cfg(not(all(tokio_unstable, feature = "tracing")))]
+    {
+        let _ = name;
+        tokio::spawn(future)
+    }
+}
+
+/// Reads the body of a rejected response, up to `max_size` bytes, replacing invalid UTF-8.
+async fn read_error_body(mut response: Response, max_size: usize) -> String {
+    let mut body = Vec::new();
+    while body.len() < max_size
+        && let Ok(Some(chunk)) = response.chunk().await
+    {
+        body.extend_from_slice(&chunk);
+    }
+    body.truncate(max_size);
+    String::from_utf8_lossy(&body).into_owned()
+}
+
+/// Returns the MIME type of a `Content-Type` header value, without its parameters, e.g.
+/// `text/event-stream` for `text/event-stream; charset=utf-8`.
+fn mime_essence(content_type: &HeaderValue) -> Option<&str> {
+    let content_type = content_type.to_str().ok()?;
+    content_type.split(';').next().map(str::trim)
+}
+
+/// Checks if the MIME type is [`MIME_EVENT_STREAM`], ignoring its case.
+pub(crate) fn is_event_stream(essence: &str) -> bool {
+    essence.eq_ignore_ascii_case(MIME_EVENT_STREAM.to_str().unwrap_or_default())
+}
+
+/// Checks if the `Content-Type` header is accepted by the config.
+pub(crate) fn check_content_type(
+    headers: &HeaderMap,
+    config: &EventSourceConfig,
+) -> Result<(), EventSourceError> {
+    let content_type = headers.get(CONTENT_TYPE);
+    if config.any_content_type
+        || content_type
+            .and_then(mime_essence)
+            .is_some_and(|essence| config.is_accepted_content_type(essence))
+    {
+        Ok(())
+    } else {
+        Err(EventSourceError::BadContentType(content_type.cloned()))
+    }
+}
+
+/// Checks the head of a response before streaming its events: its status, content type, then the
+/// validator of the config, called by `validate`, shared by the async and blocking responses.
+///
+/// A `204 No Content` isn't checked, as its body is empty: the stream ends immediately. A status
+/// not accepted is reported without body, to be read by the caller if captured.
+pub(crate) fn check_response(
+    status: StatusCode,
+    headers: &HeaderMap,
+    config: &EventSourceConfig,
+    validate: impl FnOnce(&Validator) -> Result<(), String>,
+) -> Result<(), EventSourceError> {
+    if status == StatusCode::NO_CONTENT {
+        return Ok(());
+    }
+    if !config.is_accepted(status) {
+        return Err(EventSourceError::BadStatus(status, None));
+    }
+    check_content_type(headers, config)?;
+    match &config.validator {
+        Some(validator) => validate(validator).map_err(EventSourceError::Rejected),
+        None => Ok(()),
+    }
+}
+
+/// Validates the [`Response`] and converts its body into a stream of [`Event`]s.
+///
+/// `last_event_id` seeds the last event ID, so events carry it until the server sends a new one.
+pub(crate) async fn into_event_stream(
+    response: Response,
+    last_event_id: Option<String>,
+    config: EventSourceConfig,
+) -> Result<EventStream, EventSourceError> {
+    let metadata = Metadata::of(&response);
+    let state = SharedState::new(last_event_id);
+    let parser = parse_response(response, state.clone(), config, Mode::Events).await?;
+    Ok(EventStream::new(
+        metadata,
+        state,
+        parser,
+        Parsed::into_event,
+    ))
+}
+
+/// Item produced by the parser.
+pub(crate) enum Parsed {
+    /// A dispatched [`Event`], with whether its own block had an `id` field, as opposed to carrying
+    /// the ID of a previous event.
+    Event(Event, bool),
+    /// A comment, only produced with [`Mode::Comments`] and [`Mode::Items`].
+    Comment(String),
+    /// A reconnection time, only produced with [`Mode::Items`].
+    Retry(Duration),
+    /// A line, only produced with [`Mode::Lines`].
+    Line(String),
+    /// A field name and value, only produced with [`Mode::Fields`].
+    Field(String, String),
+    /// A blank line, only produced with [`Mode::Fields`].
+    Dispatch,
+    /// A dispatched event with opaque data, only produced with [`Mode::Bytes`].
+    BytesEvent(BytesEvent),
+}
 
-        let mut line_buffer = String::new();
+impl Parsed {
+    /// Returns the event, if the item is one.
+    pub(crate) fn into_event(self) -> Option<Event> {
+        match self {
+            Parsed::Event(event, _) => Some(event),
+            _ => None,
+        }
+    }
+}
+
+/// Items produced by the parser.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Mode {
+    /// Events only.
+    Events,
+    /// Events and comments.
+    Comments,
+    /// Lines, before field parsing.
+    Lines,
+    /// Fields and dispatch boundaries, before their interpretation.
+    Fields,
+    /// Events, comments, and reconnection times.
+    Items,
+    /// Events with opaque data, not validated as UTF-8.
+    Bytes,
+}
+
+/// Validates the [`Response`] and returns a [`Parser`] of its body, producing the items of the
+/// given [`Mode`].
+///
+/// The last event ID is seeded from `state`, which is kept up to date as the lines are read.
+pub(crate) async fn parse_response(
+    response: Response,
+    state: SharedState,
+    config: EventSourceConfig,
+    mode: Mode,
+) -> Result<Parser, EventSourceError> {
+    let status = response.status();
+    if let Err(error) = check_response(status, response.headers(), &config, |validator| {
+        validator.validate(&response)
+    }) {
+        return Err(match (error, config.error_body) {
+            (EventSourceError::BadStatus(status, None), Some(max_size)) => {
+                EventSourceError::BadStatus(status, Some(read_error_body(response, max_size).await))
+            }
+            (error, _) => error,
+        });
+    }
+
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!("event_stream", url = %response.url());
+    #[cfg(feature = "tracing")]
+    tracing::debug!(parent: &span, %status, "connected to event source");
+
+    let reader = body_reader(response.bytes_stream(), &state, &config);
+    // the parser keeps the current span
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+    Ok(Parser::new(reader, state, config, mode))
+}
+
+/// Wraps the chunks of a body into a [`BodyReader`], counting the bytes read and applying the
+/// timeouts of the body, then records the connection.
+pub(crate) fn body_reader<S, B, E>(
+    body: S,
+    state: &SharedState,
+    config: &EventSourceConfig,
+) -> BodyReader
+where
+    S: Stream<Item = Result<B, E>> + Send + Unpin + 'static,
+    B: Into<Bytes>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    state.stats().record_connection();
+    let counters = Arc::clone(state.stats());
+    let body = body.map(move |result| {
+        let chunk = result.map_err(io::Error::other)?.into();
+        counters.record_bytes(chunk.len());
+        Ok::<Bytes, io::Error>(chunk)
+    });
+    let body = Watchdog::new(body, config.stale_timeout, BodyTimeout::Stale);
+    let idle_timeout = match config.idle_activity {
+        IdleActivity::Bytes => config.idle_timeout,
+        IdleActivity::Lines | IdleActivity::Events => None,
+    };
+    let reader = StreamReader::new(Watchdog::new(body, idle_timeout, BodyTimeout::Idle));
+    match config.read_buffer_capacity {
+        Some(capacity) => Box::new(tokio::io::BufReader::with_capacity(capacity, reader)),
+        None => Box::new(reader),
+    }
+}
+
+/// Parser of an event stream, yielding the [`Parsed`] items read from the body.
+///
+/// The [`Stream`] is implemented by hand over owned state, so it's [`Unpin`], and the reader can be
+/// given back with the partially read line.
+pub(crate) struct Parser {
+    reader: LineReader<BodyReader>,
+    event_buffer: EventBuffer,
+    state: SharedState,
+    config: EventSourceConfig,
+    mode: Mode,
+    /// Whether the current event must be discarded, due to an invalid line with
+    /// `InvalidUtf8Policy::SkipEvent`, its size, or its type.
+    skip_event: bool,
+    /// Sequence number of the next event.
+    sequence: u64,
+    /// Completed once the cancellation token is cancelled, if any.
+    cancelled: Option<Pin<Box<WaitForCancellationFutureOwned>>>,
+    /// Whether the stream has ended.
+    done: bool,
+    /// Deadline of [`EventSourceConfig::idle_timeout`], reset on each line or event, unless the
+    /// timeout applies to the bytes read and is handled by the [`Watchdog`] of the body.
+    idle: Option<Pin<Box<Sleep>>>,
+    /// Deadline of [`EventSourceConfig::first_event_timeout`], until the first event.
+    first_event: Option<Pin<Box<Sleep>>>,
+    /// Deadline of the next [`EventSourceConfig::heartbeat`], reset on each comment.
+    heartbeat: Option<Pin<Box<Sleep>>>,
+    /// Deadline of [`EventSourceConfig::max_duration`], ending the stream once elapsed and the
+    /// reader is waiting for more data, or the task must yield.
+    deadline: Option<Pin<Box<Sleep>>>,
+    /// Whether each line consumes the Tokio task budget, so that a burst of buffered events
+    /// doesn't starve the other tasks. Disabled for blocking reads, never pending.
+    cooperative: bool,
+    /// Span current when the parser was created, entered while parsing.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Parser {
+    /// Creates a parser of the body read by `reader`.
+    pub(crate) fn new(
+        reader: BodyReader,
+        state: SharedState,
+        config: EventSourceConfig,
+        mode: Mode,
+    ) -> Self {
         let mut event_buffer = EventBuffer::new();
+        event_buffer
+            .last_event_id
+            .clone_from(&state.lock().last_event_id);
+        let idle = config
+            .idle_timeout
+            .filter(|_| config.idle_activity != IdleActivity::Bytes)
+            .map(|timeout| Box::pin(tokio::time::sleep(timeout)));
+        let first_event = config
+            .first_event_timeout
+            .map(|timeout| Box::pin(tokio::time::sleep(timeout)));
+        let heartbeat = config
+            .heartbeat
+            .map(|timeout| Box::pin(tokio::time::sleep(timeout)));
+        let deadline = config
+            .max_duration
+            .map(|duration| Box::pin(tokio::time::sleep(duration)));
+        Self {
+            reader: LineReader::new(reader, config.max_line_length),
+            event_buffer,
+            state,
+            cancelled: config
+                .cancellation_token
+                .clone()
+                .map(|token| Box::pin(token.cancelled_owned())),
+            config,
+            mode,
+            skip_event: false,
+            sequence: 0,
+            done: false,
+            idle,
+            first_event,
+            heartbeat,
+            deadline,
+            cooperative: true,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+        }
+    }
+
+    /// Disables the consumption of the Tokio task budget, for blocking reads.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking(mut self) -> Self {
+        self.cooperative = false;
+        self
+    }
+
+    /// Returns the underlying reader, with the bytes of the line being read, and whether a leading
+    /// `\n` must be skipped, see [`LineReader::into_parts`].
+    pub(crate) fn into_parts(self) -> (BodyReader, Vec<u8>, bool) {
+        self.reader.into_parts()
+    }
+
+    /// Takes back the allocations of an event it produced, see [`EventStream::next_ref`].
+    pub(crate) fn recycle(&mut self, event: Event) {
+        self.event_buffer.recycle(event);
+    }
+
+    /// Polls the deadlines of the timeouts, completing with the error of the first one elapsed.
+    fn poll_timeout(&mut self, cx: &mut Context<'_>) -> Poll<EventError> {
+        if let Some(heartbeat) = &mut self.heartbeat
+            && heartbeat.as_mut().poll(cx).is_ready()
+        {
+            return Poll::Ready(EventError::HeartbeatMissed);
+        }
+        if let Some(idle) = &mut self.idle
+            && idle.as_mut().poll(cx).is_ready()
+        {
+            return Poll::Ready(EventError::IdleTimeout);
+        }
+        if let Some(first_event) = &mut self.first_event
+            && first_event.as_mut().poll(cx).is_ready()
+        {
+            return Poll::Ready(EventError::FirstEventTimeout);
+        }
+        Poll::Pending
+    }
+
+    /// Whether the [`EventSourceConfig::max_duration`] elapsed, if any, without polling its
+    /// deadline.
+    fn max_duration_elapsed(&self) -> bool {
+        self.deadline
+            .as_ref()
+            .is_some_and(|deadline| tokio::time::Instant::now() >= deadline.deadline())
+    }
+
+    /// Restarts the deadline of [`EventSourceConfig::idle_timeout`], if any.
+    fn reset_idle(&mut self) {
+        if let (Some(idle), Some(timeout)) = (&mut self.idle, self.config.idle_timeout) {
+            idle.as_mut().reset(tokio::time::Instant::now() + timeout);
+        }
+    }
+
+    /// Stamps the event with the current time and its sequence number, if enabled.
+    fn stamp(&mut self, event: &mut Event) {
+        if self.config.received_at {
+            event.received_at = Some(SystemTime::now());
+        }
+        if self.config.sequence {
+            event.sequence = Some(self.sequence);
+            self.sequence += 1;
+        }
+    }
+
+    /// Filters the current event by its current type, as accepted by the config or not.
+    ///
+    /// A later `event` line can accept the event again, unless its data was already dropped.
+    fn filter_event_type(&mut self) {
+        let event_type = match self.event_buffer.event_type.as_str() {
+            "" => "message",
+            event_type => event_type,
+        };
+        self.event_buffer.filtered = !self.config.is_accepted_event_type(event_type);
+        if self.event_buffer.filtered && self.event_buffer.has_data() {
+            self.skip_event = true;
+            self.event_buffer.data.clear();
+            self.event_buffer.bytes.clear();
+        }
+    }
+
+    /// Handles the blank and `data` lines in [`Mode::Bytes`], without validating them as UTF-8.
+    ///
+    /// Continues with the other lines, to be handled as usual.
+    fn handle_bytes_line(&mut self) -> ControlFlow<Option<Result<Parsed, EventError>>> {
+        let line = self.reader.line();
+        if line.is_empty() {
+            self.state
+                .lock()
+                .last_event_id
+                .clone_from(&self.event_buffer.last_event_id);
+            self.filter_event_type();
+            let skipped = std::mem::take(&mut self.skip_event);
+            let event = self.event_buffer.produce_bytes_event();
+            return ControlFlow::Break(
+                event
+                    .filter(|_| !skipped)
+                    .map(|event| Ok(Parsed::BytesEvent(event))),
+            );
+        }
+
+        let (field, value) = match memchr::memchr(b':', line) {
+            Some(colon) => (&line[..colon], &line[colon + 1..]),
+            None => (line, &b""[..]),
+        };
+        let value = value.strip_prefix(b" ").unwrap_or(value);
+        let Ok(field) = std::str::from_utf8(field) else {
+            return ControlFlow::Continue(());
+        };
+        if Field::parse(field, &self.config) != Some(Field::Data) {
+            return ControlFlow::Continue(());
+        }
+        // the data of an event not accepted isn't buffered
+        self.skip_event |= self.event_buffer.filtered;
+        if self.skip_event {
+            return ControlFlow::Break(None);
+        }
+        if let Some(max_size) = self.config.max_event_size
+            && self.event_buffer.bytes.len() + value.len() > max_size
+        {
+            self.event_buffer.bytes.clear();
+            self.skip_event = true;
+            return ControlFlow::Break(Some(Err(EventError::EventTooLarge(max_size))));
+        }
+        self.event_buffer.push_bytes(value);
+        ControlFlow::Break(None)
+    }
+
+    /// Handles the outcome of a line read, returning the item it produces, if any.
+    ///
+    /// Sets `done` when the stream must end.
+    #[allow(clippy::too_many_lines)]
+    fn handle_line(&mut self, read: ReadLine) -> Option<Result<Parsed, EventError>> {
+        match read {
+            ReadLine::Line => {}
+            ReadLine::TooLong => {
+                if self.config.long_line == LongLinePolicy::Terminate {
+                    self.done = true;
+                }
+                return Some(Err(EventError::LineTooLong(
+                    self.config.max_line_length.unwrap_or_default(),
+                )));
+            }
+            ReadLine::End if self.mode == Mode::Bytes => {
+                self.done = true;
+                self.filter_event_type();
+                let event = self.event_buffer.produce_bytes_event()?;
+                if self.skip_event {
+                    return None;
+                }
+                return match self.config.truncated_event_policy() {
+                    TruncatedEventPolicy::Discard => None,
+                    TruncatedEventPolicy::Dispatch => {
+                        self.state
+                            .lock()
+                            .last_event_id
+                            .clone_from(&event.last_event_id);
+                        Some(Ok(Parsed::BytesEvent(event)))
+                    }
+                    TruncatedEventPolicy::Error => {
+                        Some(Err(EventError::TruncatedEvent(Box::new(Event {
+                            event_type: event.event_type,
+                            data: String::from_utf8_lossy(&event.data).into_owned(),
+                            last_event_id: event.last_event_id,
+                            retry: event.retry,
+                            extra: Vec::new(),
+                            raw: None,
+                            received_at: None,
+                            sequence: None,
+                        }))))
+                    }
+                };
+            }
+            ReadLine::End => {
+                // the last event wasn't terminated by a blank line
+                self.done = true;
+                self.filter_event_type();
+                let has_id = self.event_buffer.has_id;
+                let mut event = self.event_buffer.produce_event()?;
+                if self.skip_event {
+                    return None;
+                }
+                self.stamp(&mut event);
+                return match self.config.truncated_event_policy() {
+                    TruncatedEventPolicy::Discard => None,
+                    TruncatedEventPolicy::Dispatch => {
+                        self.state
+                            .lock()
+                            .last_event_id
+                            .clone_from(&event.last_event_id);
+                        Some(Ok(Parsed::Event(event, has_id)))
+                    }
+                    TruncatedEventPolicy::Error => {
+                        Some(Err(EventError::TruncatedEvent(Box::new(event))))
+                    }
+                };
+            }
+        }
+
+        if self.mode == Mode::Bytes
+            && let ControlFlow::Break(item) = self.handle_bytes_line()
+        {
+            return item;
+        }
 
-        let stream = Box::pin(try_stream! {
-            loop {
-                line_buffer.clear();
-                let count = stream.read_line(&mut line_buffer).await.map_err(EventError::IoError)?;
-                if count == 0 {
-                    break;
+        let line = match std::str::from_utf8(self.reader.line()) {
+            Ok(line) => Cow::Borrowed(line),
+            Err(error) => match self.config.invalid_utf8 {
+                InvalidUtf8Policy::Strict => {
+                    self.done = true;
+                    return Some(Err(EventError::Utf8Error(error)));
+                }
+                InvalidUtf8Policy::Lossy => String::from_utf8_lossy(self.reader.line()),
+                InvalidUtf8Policy::SkipEvent => {
+                    self.skip_event = true;
+                    return None;
                 }
-                let line = if let Some(line) = line_buffer.strip_suffix('\n') {
-                    line
+            },
+        };
+
+        match self.mode {
+            Mode::Lines => return Some(Ok(Parsed::Line(line.into_owned()))),
+            Mode::Fields => {
+                return if line.is_empty() {
+                    Some(Ok(Parsed::Dispatch))
+                } else if line.starts_with(':') {
+                    None
                 } else {
-                    &line_buffer
+                    let (field, value) = parse_line(&line);
+                    Some(Ok(Parsed::Field(field.to_string(), value.to_string())))
                 };
+            }
+            Mode::Events | Mode::Comments | Mode::Items | Mode::Bytes => {}
+        }
+
+        if self.config.raw && !line.is_empty() {
+            self.event_buffer.push_raw(&line);
+        }
+
+        // dispatch
+        if line.is_empty() {
+            self.state
+                .lock()
+                .last_event_id
+                .clone_from(&self.event_buffer.last_event_id);
+            let has_id = self.event_buffer.has_id;
+            self.filter_event_type();
+            let skipped = std::mem::take(&mut self.skip_event);
+            let mut event = self.event_buffer.produce_event()?;
+            self.stamp(&mut event);
+            return (!skipped).then_some(Ok(Parsed::Event(event, has_id)));
+        }
 
-                // dispatch
-                if line.is_empty() {
-                    if let Some(event) = event_buffer.produce_event() {
-                        yield event;
+        if let Some(comment) = line.strip_prefix(':') {
+            if !matches!(self.mode, Mode::Comments | Mode::Items) {
+                return None;
+            }
+            let comment = comment.strip_prefix(' ').unwrap_or(comment);
+            return Some(Ok(Parsed::Comment(comment.to_string())));
+        }
+
+        let (field, value) = parse_line(&line);
+
+        match Field::parse(field, &self.config) {
+            Some(Field::Event) => {
+                self.event_buffer.set_event_type(value);
+                self.filter_event_type();
+            }
+            Some(Field::Data) => {
+                // the data of an event not accepted isn't buffered
+                self.skip_event |= self.event_buffer.filtered;
+                if self.skip_event {
+                    return None;
+                }
+                if let Some(max_size) = self.config.max_event_size
+                    && self.event_buffer.data_size_with(value) > max_size
+                {
+                    self.event_buffer.data.clear();
+                    self.skip_event = true;
+                    return Some(Err(EventError::EventTooLarge(max_size)));
+                }
+                self.event_buffer.push_data(value);
+            }
+            Some(Field::Id) => {
+                if !self.event_buffer.set_id(value) {
+                    warn(&self.config, ParseWarning::NullInId(value.to_string()));
+                }
+            }
+            Some(Field::Retry) => match parse_retry(value, self.config.parse_mode) {
+                Ok(retry) => {
+                    self.state.lock().retry = Some(retry);
+                    self.event_buffer.set_retry(retry);
+                    if self.mode == Mode::Items {
+                        return Some(Ok(Parsed::Retry(retry)));
                     }
-                    continue;
                 }
+                Err(_) => warn(&self.config, ParseWarning::InvalidRetry(value.to_string())),
+            },
+            None => {
+                if self.config.unknown_fields {
+                    self.event_buffer.push_extra(field, value);
+                } else {
+                    warn(&self.config, ParseWarning::UnknownField(field.to_string()));
+                }
+            }
+        }
+        None
+    }
+}
 
-                let (field, value) = parse_line(line);
+impl Stream for Parser {
+    type Item = Result<Parsed, EventError>;
 
-                match field {
-                    "event" => {
-                        event_buffer.set_event_type(value);
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        #[cfg(feature = "tracing")]
+        let _entered = this.span.clone().entered();
+        if !this.done
+            && let Some(cancelled) = &mut this.cancelled
+            && cancelled.as_mut().poll(cx).is_ready()
+        {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("event stream cancelled");
+            this.done = true;
+        }
+        while !this.done {
+            // each line read consumes a unit of budget, even from the buffer: once the task runs
+            // out of it, it yields to the runtime
+            let coop = if this.cooperative {
+                match tokio::task::coop::poll_proceed(cx) {
+                    Poll::Ready(coop) => Some(coop),
+                    // a source never pending ends once it must yield, after the maximum duration
+                    Poll::Pending if this.max_duration_elapsed() => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("maximum duration of event stream elapsed");
+                        this.done = true;
+                        break;
                     }
-                    "data" => {
-                        event_buffer.push_data(value);
+                    Poll::Pending => return Poll::Pending,
+                }
+            } else {
+                None
+            };
+            let read = this.reader.poll_read_line(cx);
+            if read.is_ready()
+                && let Some(coop) = coop
+            {
+                coop.made_progress();
+            }
+            let item = match read {
+                Poll::Ready(Ok(read)) => {
+                    if this.config.idle_activity == IdleActivity::Lines {
+                        this.reset_idle();
                     }
-                    "id" => {
-                        event_buffer.set_id(value);
+                    if let (Some(heartbeat), Some(timeout)) =
+                        (&mut this.heartbeat, this.config.heartbeat)
+                        && read == ReadLine::Line
+                        && this.reader.line().first() == Some(&b':')
+                    {
+                        heartbeat
+                            .as_mut()
+                            .reset(tokio::time::Instant::now() + timeout);
                     }
-                    "retry" => {
-                        if let Ok(millis) = value.parse() {
-                            event_buffer.set_retry(Duration::from_millis(millis));
-                        }
+                    this.handle_line(read)
+                }
+                Poll::Ready(Err(error)) => {
+                    this.done = true;
+                    Some(Err(into_event_error(error)))
+                }
+                Poll::Pending => {
+                    // the events already received are dispatched before ending
+                    if let Some(deadline) = &mut this.deadline
+                        && deadline.as_mut().poll(cx).is_ready()
+                    {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("maximum duration of event stream elapsed");
+                        this.done = true;
+                        break;
                     }
-                    _ => {}
+                    let error = ready!(this.poll_timeout(cx));
+                    this.done = true;
+                    Some(Err(error))
+                }
+            };
+            if item.is_some() {
+                let event_type = match &item {
+                    Some(Ok(Parsed::Event(event, _))) => Some(&event.event_type),
+                    Some(Ok(Parsed::BytesEvent(event))) => Some(&event.event_type),
+                    _ => None,
+                };
+                if let Some(event_type) = event_type {
+                    this.state.stats().record_event(event_type);
+                    this.reset_idle();
+                    this.first_event = None;
                 }
+                #[cfg(feature = "tracing")]
+                trace_item(item.as_ref());
+                return Poll::Ready(item);
             }
-        });
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!("event stream ended");
+        Poll::Ready(None)
+    }
+}
 
-        Ok(stream)
+/// Emits a `tracing` event for a dispatched event, or an error.
+#[cfg(feature = "tracing")]
+fn trace_item(item: Option<&Result<Parsed, EventError>>) {
+    match item {
+        Some(Ok(Parsed::Event(event, _))) => tracing::trace!(
+            event_type = &*event.event_type,
+            data_length = event.data.len(),
+            id = event.last_event_id.as_deref(),
+            "event dispatched"
+        ),
+        Some(Ok(Parsed::BytesEvent(event))) => tracing::trace!(
+            event_type = &*event.event_type,
+            data_length = event.data.len(),
+            id = event.last_event_id.as_deref(),
+            "event dispatched"
+        ),
+        Some(Err(error)) => tracing::debug!(%error, "event stream error"),
+        _ => {}
     }
 }
 
+/// Creates an event of the given type and data, without the other fields, for the tests.
+#[cfg(test)]
+pub(crate) fn event_of_type(event_type: &str, data: &str) -> Event {
+    Event {
+        event_type: event_type.into(),
+        data: data.to_string(),
+        last_event_id: None,
+        retry: None,
+        extra: Vec::new(),
+        raw: None,
+        received_at: None,
+        sequence: None,
+    }
+}
+
+/// Creates a `message` event with the given data, for the tests.
+#[cfg(test)]
+pub(crate) fn event(data: &str) -> Event {
+    event_of_type("message", data)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,4 +1932,221 @@ mod tests {
         assert_eq!(field, "data");
         assert_eq!(value, "data with : inside");
     }
+
+    #[test]
+    fn accept_event_stream_with_parameters() {
+        let is_event_stream =
+            |value| mime_essence(&HeaderValue::from_static(value)).is_some_and(is_event_stream);
+        assert!(is_event_stream("text/event-stream"));
+        assert!(is_event_stream("text/event-stream; charset=utf-8"));
+        assert!(is_event_stream("Text/Event-Stream ;charset=UTF-8"));
+        assert!(!is_event_stream("text/event-streams"));
+        assert!(!is_event_stream("text/plain; text/event-stream"));
+    }
+
+    #[test]
+    fn reserve_size_of_last_event_data() {
+        let mut buffer = EventBuffer::new();
+        buffer.push_data(&"a".repeat(100));
+        let event = buffer.produce_event().unwrap();
+        assert_eq!(event.data.len(), 100);
+
+        buffer.push_data("b");
+        assert!(buffer.data.capacity() >= 101);
+    }
+
+    #[test]
+    fn intern_event_types() {
+        let mut buffer = EventBuffer::new();
+        let mut produce = |event_type: &str| {
+            buffer.set_event_type(event_type);
+            buffer.push_data("data");
+            buffer.produce_event().unwrap().event_type
+        };
+
+        let message = produce("");
+        assert_eq!(&*message, "message");
+        assert!(Arc::ptr_eq(&message, &produce("message")));
+        let update = produce("update");
+        assert_eq!(&*update, "update");
+        assert!(Arc::ptr_eq(&update, &produce("update")));
+
+        for index in 0..MAX_INTERNED_EVENT_TYPES {
+            produce(&format!("type-{index}"));
+        }
+        assert!(!Arc::ptr_eq(&produce("overflow"), &produce("overflow")));
+        assert!(Arc::ptr_eq(&update, &produce("update")));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_retry_as_millis() {
+        let mut buffer = EventBuffer::new();
+        buffer.set_retry(Duration::from_millis(1500));
+        buffer.push_data("data");
+        let event = buffer.produce_event().unwrap();
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["retry"], 1500);
+        assert_eq!(serde_json::from_value::<Event>(json).unwrap(), event);
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn decode_base64_data() {
+        let mut buffer = EventBuffer::new();
+        buffer.push_data("AAEC");
+        buffer.push_data("/w");
+        let event = buffer.produce_event().unwrap();
+        assert_eq!(event.decode_base64().unwrap().as_ref(), [0, 1, 2, 255]);
+
+        let mut buffer = EventBuffer::new();
+        buffer.push_data("not base64");
+        let event = buffer.produce_event().unwrap();
+        assert!(matches!(
+            event.decode_base64(),
+            Err(EventError::Base64Error(_))
+        ));
+    }
+
+    #[test]
+    fn parse_retry_according_to_mode() {
+        let millis = |millis| Ok(Duration::from_millis(millis));
+        let not_digits = Err(InvalidRetry::NotDigits);
+        assert_eq!(parse_retry("5000", ParseMode::Strict), millis(5000));
+        assert_eq!(parse_retry("5000x", ParseMode::Strict), not_digits);
+        assert_eq!(parse_retry("+5000", ParseMode::Strict), not_digits);
+        assert_eq!(parse_retry(" 5000", ParseMode::Strict), not_digits);
+        assert_eq!(parse_retry("", ParseMode::Strict), not_digits);
+        assert_eq!(parse_retry(" 5000ms ", ParseMode::Lenient), millis(5000));
+        assert_eq!(parse_retry("ms", ParseMode::Lenient), not_digits);
+    }
+
+    #[test]
+    fn reject_overflowing_retry() {
+        assert_eq!(
+            parse_retry(&u64::MAX.to_string(), ParseMode::Strict),
+            Ok(Duration::from_millis(u64::MAX))
+        );
+        assert_eq!(
+            parse_retry("99999999999999999999999", ParseMode::Strict),
+            Err(InvalidRetry::Overflow)
+        );
+    }
+
+    #[test]
+    fn carry_last_event_id_until_changed() {
+        let mut buffer = EventBuffer::new();
+        buffer.set_id("1");
+        buffer.push_data("first");
+        let first = buffer.produce_event().unwrap();
+        assert_eq!(first.last_event_id.as_deref(), Some("1"));
+
+        buffer.push_data("second");
+        let second = buffer.produce_event().unwrap();
+        assert_eq!(second.last_event_id.as_deref(), Some("1"));
+
+        buffer.set_id("2");
+        assert!(buffer.produce_event().is_none());
+        buffer.push_data("third");
+        let third = buffer.produce_event().unwrap();
+        assert_eq!(third.last_event_id.as_deref(), Some("2"));
+
+        buffer.set_id("");
+        buffer.push_data("fourth");
+        let fourth = buffer.produce_event().unwrap();
+        assert_eq!(fourth.last_event_id, None);
+    }
+
+    #[test]
+    fn ignore_id_with_null() {
+        let mut buffer = EventBuffer::new();
+        buffer.set_id("1");
+        buffer.set_id("2\0");
+        buffer.push_data("data");
+        let event = buffer.produce_event().unwrap();
+        assert_eq!(event.last_event_id.as_deref(), Some("1"));
+
+        buffer.set_id("\0");
+        assert!(!buffer.has_id);
+    }
+
+    async fn read_lines(chunks: &[&'static [u8]]) -> Vec<String> {
+        read_lines_up_to(chunks, None).await
+    }
+
+    /// Reads the lines, with `!` standing in for the ones too long.
+    async fn read_lines_up_to(chunks: &[&'static [u8]], max_length: Option<usize>) -> Vec<String> {
+        let chunks = chunks.iter().map(|chunk| Ok::<_, io::Error>(*chunk));
+        let mut reader = LineReader::new(StreamReader::new(tokio_stream::iter(chunks)), max_length);
+        let mut lines = Vec::new();
+        loop {
+            let read = std::future::poll_fn(|cx| reader.poll_read_line(cx)).await;
+            match read.unwrap() {
+                ReadLine::Line => lines.push(String::from_utf8(reader.line().to_vec()).unwrap()),
+                ReadLine::TooLong => lines.push("!".to_string()),
+                ReadLine::End => break,
+            }
+        }
+        lines
+    }
+
+    #[tokio::test]
+    async fn split_lines_on_all_terminators() {
+        let lines = read_lines(&[b"lf\ncrlf\r\ncr\rlast"]).await;
+        assert_eq!(lines, ["lf", "crlf", "cr", "last"]);
+
+        let lines = read_lines(&[b"\r\n\r\r\n\n"]).await;
+        assert_eq!(lines, ["", "", "", ""]);
+    }
+
+    #[tokio::test]
+    async fn split_lines_across_chunks() {
+        let lines = read_lines(&[b"first\r", b"\nsecond", b" line\r", b"third\n"]).await;
+        assert_eq!(lines, ["first", "second line", "third"]);
+    }
+
+    #[tokio::test]
+    async fn find_terminators_at_any_chunk_boundary() {
+        const BODY: &[u8] = b"lf\ncrlf\r\ncr\r\r\n\nlast";
+        let expected = read_lines(&[BODY]).await;
+        for split in 0..=BODY.len() {
+            let (head, tail) = BODY.split_at(split);
+            assert_eq!(
+                read_lines(&[head, tail]).await,
+                expected,
+                "split at {split}"
+            );
+        }
+        let bytes = BODY.chunks(1).collect::<Vec<_>>();
+        assert_eq!(read_lines(&bytes).await, expected);
+    }
+
+    #[tokio::test]
+    async fn reassemble_code_points_across_chunks() {
+        let lines = read_lines(&[b"caf\xC3", b"\xA9 \xE2\x82", b"\xAC\n"]).await;
+        assert_eq!(lines, ["café €"]);
+    }
+
+    #[tokio::test]
+    async fn skip_too_long_lines() {
+        let lines =
+            read_lines_up_to(&[b"1234\n12345\n123", b"456\r\n12", b"\n123456"], Some(4)).await;
+        assert_eq!(lines, ["1234", "!", "!", "12", "!"]);
+
+        let lines = read_lines_up_to(&[b"12345", b"678", b"9\rok\n"], Some(4)).await;
+        assert_eq!(lines, ["!", "ok"]);
+    }
+
+    #[tokio::test]
+    async fn strip_leading_bom() {
+        let lines = read_lines(&[b"\xEF\xBB\xBFdata\n\xEF\xBB\xBFdata\n"]).await;
+        assert_eq!(lines, ["data", "\u{FEFF}data"]);
+
+        let lines = read_lines(&[b"\xEF", b"\xBB", b"\xBFdata\n"]).await;
+        assert_eq!(lines, ["data"]);
+
+        let lines = read_lines(&[b"\xEF\xBB\xBF\xEF\xBB\xBF\n"]).await;
+        assert_eq!(lines, ["\u{FEFF}"]);
+    }
 }
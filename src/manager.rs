@@ -0,0 +1,253 @@
+//! Supervision of many concurrent subscriptions to event sources.
+//!
+//! A [`SubscriptionManager`] owns a [`Client`] and spawns one task per subscription. Each task
+//! drives a [`ReconnectingEventSource`] and forwards its items to a single merged channel,
+//! tagged with the [`SubscriptionId`] of their subscription. When a subscription's stream fails,
+//! e.g. with a server error status, it's restarted according to the [`RestartPolicy`].
+//!
+//! ```rust,no_run
+//! use reqwest_sse::manager::SubscriptionManager;
+//!
+//! # async fn run() {
+//! let (manager, mut events) = SubscriptionManager::new(reqwest::Client::new(), 64);
+//! let _story = manager.subscribe("https://sse.test-free.online/api/story");
+//!
+//! while let Some((id, event)) = events.recv().await {
+//!     println!("{id}: {event:?}");
+//! }
+//! # }
+//! ```
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use reqwest::{Client, IntoUrl, RequestBuilder, StatusCode};
+use tokio::{
+    sync::mpsc,
+    task::{JoinError, JoinHandle},
+};
+use tokio_stream::StreamExt;
+
+use crate::{
+    Event,
+    error::{EventError, EventSourceError},
+    reconnect::{LastEventIdStore, ReconnectingEventSource},
+};
+
+/// Identifier of a subscription, unique per [`SubscriptionManager`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct SubscriptionId(u64);
+
+impl Display for SubscriptionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Item of the merged channel: an event or an error, with the subscription it comes from.
+pub type SubscriptionItem = (SubscriptionId, Result<Event, EventError>);
+
+/// Policy applied when a subscription's stream fails.
+///
+/// A stream fails when it ends with an error, e.g. a server error status, unless the server
+/// rejected the subscription: a client error status but `429 Too Many Requests`, a bad content
+/// type, or a rejection by the validator. A stream ending without error, e.g. on a `204 No Content`, is never restarted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RestartPolicy {
+    /// The subscription ends with its stream.
+    Never,
+    /// The subscription is restarted after the delay, without limit.
+    Always(Duration),
+    /// The subscription is restarted after the delay, up to the given number of times.
+    Limited(u32, Duration),
+}
+
+/// Spawns and supervises subscriptions, merging their events in one channel.
+///
+/// Must be used within a Tokio runtime.
+pub struct SubscriptionManager {
+    client: Client,
+    sender: mpsc::Sender<SubscriptionItem>,
+    restart_policy: RestartPolicy,
+    next_id: AtomicU64,
+}
+
+impl SubscriptionManager {
+    /// Creates a new [`SubscriptionManager`] and the receiving half of its merged channel, able to
+    /// buffer `capacity` items.
+    ///
+    /// By default, subscriptions are restarted after [`crate::reconnect::DEFAULT_RETRY`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    #[must_use]
+    pub fn new(client: Client, capacity: usize) -> (Self, mpsc::Receiver<SubscriptionItem>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let manager = Self {
+            client,
+            sender,
+            restart_policy: RestartPolicy::Always(crate::reconnect::DEFAULT_RETRY),
+            next_id: AtomicU64::new(0),
+        };
+        (manager, receiver)
+    }
+
+    /// Sets the [`RestartPolicy`] of the subscriptions created afterward.
+    #[must_use]
+    pub fn restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = restart_policy;
+        self
+    }
+
+    /// Returns the [`Client`] used by the subscriptions.
+    #[must_use]
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Subscribes to the event source at the given URL with a `GET` request.
+    pub fn subscribe(&self, url: impl IntoUrl) -> SubscriptionHandle {
+        self.subscribe_request(self.client.get(url))
+    }
+
+    /// Subscribes to the event source reached by the given request.
+    ///
+    /// The request must be cloneable (see [`RequestBuilder::try_clone`]) to be restarted.
+    pub fn subscribe_request(&self, request: RequestBuilder) -> SubscriptionHandle {
+        self.subscribe_with(request, |source| source)
+    }
+
+    /// Subscribes to the event source reached by the given request, with the
+    /// [`ReconnectingEventSource`] configured by `configure` on each start, e.g. to set its
+    /// [`EventSourceConfig`](crate::config::EventSourceConfig), its
+    /// [`CircuitBreaker`](crate::breaker::CircuitBreaker) or its [`LastEventIdStore`].
+    ///
+    /// The request must be cloneable (see [`RequestBuilder::try_clone`]) to be restarted.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use reqwest_sse::{config::EventSourceConfig, manager::SubscriptionManager};
+    ///
+    /// # async fn run() {
+    /// let (manager, mut events) = SubscriptionManager::new(reqwest::Client::new(), 64);
+    /// let request = manager.client().get("https://sse.test-free.online/api/story");
+    /// let _story = manager.subscribe_with(request, |source| {
+    ///     source.config(EventSourceConfig::new().stale_timeout(Duration::from_secs(30)))
+    /// });
+    /// # }
+    /// ```
+    pub fn subscribe_with<F, S>(&self, request: RequestBuilder, configure: F) -> SubscriptionHandle
+    where
+        F: Fn(ReconnectingEventSource) -> ReconnectingEventSource<S> + Send + 'static,
+        S: LastEventIdStore + 'static,
+    {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let sender = self.sender.clone();
+        let restart_policy = self.restart_policy;
+
+        let name = format!("reqwest-sse subscription {id}");
+        let task = crate::spawn(&name, async move {
+            let mut request = Some(request);
+            let mut last_event_id: Option<String> = None;
+            let mut restarts = 0;
+            loop {
+                let attempt = match request.as_ref().and_then(RequestBuilder::try_clone) {
+                    Some(attempt) => attempt,
+                    None => match request.take() {
+                        Some(attempt) => attempt,
+                        None => break,
+                    },
+                };
+                let source = ReconnectingEventSource::new(attempt);
+                let source = match &last_event_id {
+                    Some(last_event_id) => source.last_event_id(last_event_id.clone()),
+                    None => source,
+                };
+
+                let mut events = configure(source).events();
+                let mut failed = false;
+                while let Some(result) = events.next().await {
+                    // the last item tells whether the stream failed
+                    failed = match &result {
+                        Ok(event) => {
+                            last_event_id.clone_from(&event.last_event_id);
+                            false
+                        }
+                        Err(error) => is_failure(error),
+                    };
+                    if sender.send((id, result)).await.is_err() {
+                        // nobody is listening anymore
+                        return;
+                    }
+                }
+
+                if !failed {
+                    break;
+                }
+                let delay = match restart_policy {
+                    RestartPolicy::Never => break,
+                    RestartPolicy::Limited(max_restarts, _) if restarts >= max_restarts => break,
+                    RestartPolicy::Always(delay) | RestartPolicy::Limited(_, delay) => delay,
+                };
+                restarts += 1;
+                tokio::time::sleep(delay).await;
+            }
+        });
+
+        SubscriptionHandle { id, task }
+    }
+}
+
+/// Whether an error ending a stream is a failure to restart from, rather than a rejection.
+fn is_failure(error: &EventError) -> bool {
+    match error {
+        EventError::SourceError(
+            EventSourceError::BadContentType(_) | EventSourceError::Rejected(_),
+        ) => false,
+        EventError::SourceError(EventSourceError::BadStatus(status, _)) => {
+            !status.is_client_error() || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => true,
+    }
+}
+
+/// Handle on a subscription spawned by a [`SubscriptionManager`].
+///
+/// Dropping the handle doesn't stop the subscription, use [`SubscriptionHandle::abort`].
+#[derive(Debug)]
+pub struct SubscriptionHandle {
+    id: SubscriptionId,
+    task: JoinHandle<()>,
+}
+
+impl SubscriptionHandle {
+    /// Returns the [`SubscriptionId`] tagging the items of this subscription.
+    #[must_use]
+    pub fn id(&self) -> SubscriptionId {
+        self.id
+    }
+
+    /// Stops the subscription.
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+
+    /// Checks if the subscription has ended.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Waits for the subscription to end.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`JoinError`] if the subscription was aborted.
+    pub async fn wait(self) -> Result<(), JoinError> {
+        self.task.await
+    }
+}
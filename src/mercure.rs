@@ -0,0 +1,323 @@
+//! Helpers for the [Mercure](https://mercure.rocks/spec) protocol.
+//!
+//! Enabled by the `mercure` feature. A Mercure hub publishes the updates of topics over SSE:
+//!
+//! - The hub of a resource is discovered from the `Link` headers of its response, see
+//!   [`Discovery`].
+//! - A [`Subscription`] builds the URL subscribing to topics on the hub.
+//! - An [`Updates`] stream reads the [`Update`]s from the events of the subscription.
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::{EventSourceRequest, mercure::{Discovery, Subscription, Updates}};
+//!
+//! # async fn run() {
+//! let client = reqwest::Client::new();
+//! let response = client.get("https://example.com/books/1").send().await.unwrap();
+//! let discovery = Discovery::from_response(&response).unwrap();
+//!
+//! let subscription = Subscription::new(discovery.hub).topic("https://example.com/books/{id}");
+//! let events = client.get(subscription.url()).events().await.unwrap();
+//! let mut updates = Updates::new(events, &subscription);
+//!
+//! while let Some(Ok(update)) = updates.next().await {
+//!     println!("{:?}: {}", update.id, update.data);
+//! }
+//! # }
+//! ```
+use std::{
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures_core::Stream;
+use reqwest::{
+    Response, Url,
+    header::{HeaderMap, LINK},
+};
+
+use crate::{Event, error::EventError};
+
+/// Relation type of the links to Mercure hubs.
+const HUB_RELATION: &str = "mercure";
+
+/// Hub and canonical topic of a resource, discovered from the `Link` headers of its response.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Discovery {
+    /// The first hub advertised, by a `rel="mercure"` link.
+    pub hub: Url,
+    /// The canonical topic of the resource, advertised by a `rel="self"` link.
+    pub topic: Option<Url>,
+}
+
+impl Discovery {
+    /// Discovers the hub from the headers of the response, resolving relative links against its
+    /// URL.
+    ///
+    /// Returns `None` if no hub is advertised.
+    #[must_use]
+    pub fn from_response(response: &Response) -> Option<Self> {
+        Self::from_headers(response.url(), response.headers())
+    }
+
+    /// Discovers the hub from the headers, resolving relative links against the base URL.
+    ///
+    /// Returns `None` if no hub is advertised.
+    #[must_use]
+    pub fn from_headers(base: &Url, headers: &HeaderMap) -> Option<Self> {
+        let mut hub = None;
+        let mut topic = None;
+        let links = headers
+            .get_all(LINK)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(parse_links);
+        for (target, relations) in links {
+            let Ok(url) = base.join(target) else {
+                continue;
+            };
+            for relation in relations.split_ascii_whitespace() {
+                if relation.eq_ignore_ascii_case(HUB_RELATION) && hub.is_none() {
+                    hub = Some(url.clone());
+                } else if relation.eq_ignore_ascii_case("self") && topic.is_none() {
+                    topic = Some(url.clone());
+                }
+            }
+        }
+        Some(Self { hub: hub?, topic })
+    }
+}
+
+/// Parses the links of a `Link` header value into their target and `rel` parameter.
+fn parse_links(value: &str) -> Vec<(&str, &str)> {
+    let mut links = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let target = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        // Parameters run until the next link, a comma outside of a quoted string.
+        let mut quoted = false;
+        let params_end = rest
+            .char_indices()
+            .find(|&(_, character)| {
+                if character == '"' {
+                    quoted = !quoted;
+                }
+                character == ',' && !quoted
+            })
+            .map_or(rest.len(), |(index, _)| index);
+        let relations = rest[..params_end]
+            .split(';')
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("rel"))
+            .map_or("", |(_, value)| value.trim().trim_matches('"'));
+        links.push((target, relations));
+        rest = &rest[params_end..];
+    }
+    links
+}
+
+/// Subscription to topics on a hub.
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    hub: Url,
+    topics: Vec<String>,
+    last_event_id: Option<String>,
+}
+
+impl Subscription {
+    /// Starts a subscription to the hub, without topics.
+    #[must_use]
+    pub fn new(hub: Url) -> Self {
+        Self {
+            hub,
+            topics: Vec::new(),
+            last_event_id: None,
+        }
+    }
+
+    /// Adds a topic, either an URI or an URI template.
+    #[must_use]
+    pub fn topic(mut self, topic: impl Into<String>) -> Self {
+        self.topics.push(topic.into());
+        self
+    }
+
+    /// Asks the hub for the updates following the one with this ID.
+    #[must_use]
+    pub fn last_event_id(mut self, last_event_id: impl Into<String>) -> Self {
+        self.last_event_id = Some(last_event_id.into());
+        self
+    }
+
+    /// Returns the topics subscribed to.
+    #[must_use]
+    pub fn topics(&self) -> &[String] {
+        &self.topics
+    }
+
+    /// Returns the URL of the subscription.
+    #[must_use]
+    pub fn url(&self) -> Url {
+        let mut url = self.hub.clone();
+        {
+            let mut query = url.query_pairs_mut();
+            for topic in &self.topics {
+                query.append_pair("topic", topic);
+            }
+            if let Some(last_event_id) = &self.last_event_id {
+                query.append_pair("lastEventID", last_event_id);
+            }
+        }
+        url
+    }
+}
+
+/// Update published by a hub.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Update {
+    /// The ID of the update.
+    pub id: Option<String>,
+    /// The type of the update, `None` for the default `message` type.
+    pub event_type: Option<String>,
+    /// The content of the update.
+    pub data: String,
+    /// The topic of the update.
+    ///
+    /// Hubs don't send the topic of updates, so it's only known for subscriptions to a single
+    /// topic, `None` otherwise.
+    pub topic: Option<String>,
+}
+
+/// Stream of the updates read from the events of a subscription.
+#[derive(Debug)]
+pub struct Updates<S> {
+    stream: S,
+    topic: Option<String>,
+}
+
+impl<S> Updates<S> {
+    /// Wraps the stream of events of the subscription.
+    #[must_use]
+    pub fn new(stream: S, subscription: &Subscription) -> Self {
+        let topic = match subscription.topics() {
+            [topic] => Some(topic.clone()),
+            _ => None,
+        };
+        Self { stream, topic }
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> Stream for Updates<S>
+where
+    S: Stream<Item = Result<Event, EventError>> + Unpin,
+{
+    type Item = Result<Update, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(Pin::new(&mut self.stream).poll_next(cx));
+        Poll::Ready(item.map(|event| {
+            let event = event?;
+            Ok(Update {
+                id: event.last_event_id,
+                event_type: Some(event.event_type)
+                    .filter(|event_type| &**event_type != "message")
+                    .map(|event_type| event_type.to_string()),
+                data: event.data,
+                topic: self.topic.clone(),
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    #[test]
+    fn discover_hub_from_link_headers() {
+        let base = Url::parse("https://example.com/books/1").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.append(
+            LINK,
+            HeaderValue::from_static(r#"</docs>; rel="help; index", </books/1>; rel=self"#),
+        );
+        headers.append(
+            LINK,
+            HeaderValue::from_static(
+                r#"<https://hub.example.com/.well-known/mercure>; rel="mercure""#,
+            ),
+        );
+
+        let discovery = Discovery::from_headers(&base, &headers).unwrap();
+        assert_eq!(
+            discovery.hub.as_str(),
+            "https://hub.example.com/.well-known/mercure"
+        );
+        assert_eq!(
+            discovery.topic.unwrap().as_str(),
+            "https://example.com/books/1"
+        );
+    }
+
+    #[test]
+    fn discover_nothing_without_hub() {
+        let base = Url::parse("https://example.com/books/1").unwrap();
+        let mut headers = HeaderMap::new();
+        headers.append(LINK, HeaderValue::from_static("</books/1>; rel=self"));
+
+        assert!(Discovery::from_headers(&base, &headers).is_none());
+    }
+
+    #[test]
+    fn build_subscription_url() {
+        let hub = Url::parse("https://hub.example.com/.well-known/mercure").unwrap();
+        let url = Subscription::new(hub)
+            .topic("https://example.com/books/{id}")
+            .topic("https://example.com/users/dunglas")
+            .last_event_id("urn:uuid:1")
+            .url();
+
+        assert_eq!(
+            url.as_str(),
+            "https://hub.example.com/.well-known/mercure\
+             ?topic=https%3A%2F%2Fexample.com%2Fbooks%2F%7Bid%7D\
+             &topic=https%3A%2F%2Fexample.com%2Fusers%2Fdunglas\
+             &lastEventID=urn%3Auuid%3A1"
+        );
+    }
+
+    #[tokio::test]
+    async fn read_updates_of_single_topic() {
+        let hub = Url::parse("https://hub.example.com/.well-known/mercure").unwrap();
+        let subscription = Subscription::new(hub).topic("https://example.com/books/1");
+        let events = tokio_stream::iter(vec![Ok(Event {
+            last_event_id: Some("urn:uuid:1".to_string()),
+            ..crate::event("{}")
+        })]);
+        let mut updates = Updates::new(events, &subscription);
+
+        assert_eq!(
+            updates.next().await.unwrap().unwrap(),
+            Update {
+                id: Some("urn:uuid:1".to_string()),
+                event_type: None,
+                data: "{}".to_string(),
+                topic: Some("https://example.com/books/1".to_string()),
+            }
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Pausing and resuming the consumption of a stream without closing it.
+//!
+//! While a [`Pausable`] stream is paused, it doesn't poll the stream it wraps, so no bytes are
+//! read from the connection and the server-sent data is left buffered by the transport.
+//!
+//! The timeouts of the wrapped stream, e.g.
+//! [`EventSourceConfig::idle_timeout`](crate::config::EventSourceConfig::idle_timeout), keep
+//! running while paused. On resume, the data received meanwhile is read first, and counts as
+//! activity, but if none came for longer than a timeout, the stream yields its error right
+//! away, and a [`ReconnectingEvents`](crate::reconnect::ReconnectingEvents) stream reconnects.
+//! Pauses are meant to be shorter than the timeouts.
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::{EventSource, pause::Pausable};
+//!
+//! # async fn run() {
+//! let events = reqwest::get("https://sse.test-free.online/api/story")
+//!     .await.unwrap()
+//!     .events()
+//!     .await.unwrap();
+//! let mut events = Pausable::new(events);
+//! let handle = events.handle();
+//!
+//! // e.g. from the UI task
+//! handle.pause();
+//! handle.resume();
+//!
+//! while let Some(Ok(event)) = events.next().await {
+//!     println!("{event:?}");
+//! }
+//! # }
+//! ```
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
+};
+
+use futures_core::Stream;
+
+#[derive(Debug, Default)]
+struct State {
+    paused: bool,
+    waker: Option<Waker>,
+}
+
+/// Handle pausing and resuming a [`Pausable`] stream, possibly from another task.
+#[derive(Debug, Clone, Default)]
+pub struct PauseHandle {
+    state: Arc<Mutex<State>>,
+}
+
+impl PauseHandle {
+    /// Pauses the stream: it stays pending until resumed.
+    pub fn pause(&self) {
+        self.lock().paused = true;
+    }
+
+    /// Resumes the stream, waking up the task waiting for it.
+    pub fn resume(&self) {
+        let waker = {
+            let mut state = self.lock();
+            state.paused = false;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Checks if the stream is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Stream adapter that can be paused and resumed.
+#[derive(Debug)]
+pub struct Pausable<S> {
+    stream: S,
+    handle: PauseHandle,
+}
+
+impl<S> Pausable<S> {
+    /// Wraps the stream, initially running.
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            handle: PauseHandle::default(),
+        }
+    }
+
+    /// Returns a [`PauseHandle`] controlling this stream.
+    pub fn handle(&self) -> PauseHandle {
+        self.handle.clone()
+    }
+
+    /// Pauses the stream: it stays pending until resumed.
+    pub fn pause(&self) {
+        self.handle.pause();
+    }
+
+    /// Resumes the stream.
+    pub fn resume(&self) {
+        self.handle.resume();
+    }
+
+    /// Checks if the stream is paused.
+    pub fn is_paused(&self) -> bool {
+        self.handle.is_paused()
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Stream + Unpin> Stream for Pausable<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        {
+            let mut state = self.handle.lock();
+            if state.paused {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        Pin::new(&mut self.stream).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stay_pending_while_paused() {
+        let mut stream = Pausable::new(tokio_stream::iter([1, 2]));
+        let handle = stream.handle();
+
+        assert_eq!(stream.next().await, Some(1));
+
+        handle.pause();
+        assert!(stream.is_paused());
+        let next = tokio::time::timeout(Duration::from_millis(10), stream.next()).await;
+        assert!(next.is_err());
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            handle.resume();
+        });
+        assert_eq!(stream.next().await, Some(2));
+        assert_eq!(stream.next().await, None);
+    }
+}
@@ -0,0 +1,775 @@
+//! Event streams that reconnect automatically, like the browser `EventSource`.
+//!
+//! A [`ReconnectingEventSource`] owns the [`RequestBuilder`] used to reach the event source, so it
+//! can send it again whenever the connection is lost. Every attempt carries the `Last-Event-ID`
+//! header of the last received event, letting the server resume where the previous connection
+//! stopped. The last event ID can be persisted across runs with a [`LastEventIdStore`], and
+//! reconnections can be frozen by a [`CircuitBreaker`] while the event source is down.
+//!
+//! Redirections can be followed by the source itself (see
+//! [`ReconnectingEventSource::follow_redirects`]), so permanent ones are remembered and used for
+//! the following connections.
+//!
+//! Events replayed by a server after a reconnection can be filtered out by ID (see
+//! [`ReconnectingEventSource::deduplicate`]).
+//!
+//! The stream can be shut down from another task with a [`CancellationToken`] (see
+//! [`ReconnectingEventSource::cancellation_token`]).
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::reconnect::ReconnectingEventSource;
+//!
+//! # async fn run() {
+//! let request = reqwest::Client::new().get("https://sse.test-free.online/api/story");
+//! let mut events = ReconnectingEventSource::new(request)
+//!     .last_event_id("42")
+//!     .events();
+//!
+//! while let Some(event) = events.next().await {
+//!     println!("{event:?}");
+//! }
+//! # }
+//! ```
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+
+use futures_core::Stream;
+use reqwest::{
+    Method, Request, RequestBuilder, Response, StatusCode, Url,
+    header::{
+        AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION,
+        PROXY_AUTHORIZATION, TRANSFER_ENCODING,
+    },
+};
+use tokio::time::Sleep;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+use crate::{
+    Event, LAST_EVENT_ID, Mode, Parsed, Parser,
+    breaker::CircuitBreaker,
+    config::EventSourceConfig,
+    error::{EventError, EventSourceError},
+    parse_response,
+    stats::{EventTypeStats, Stats, StreamStats},
+    stream::SharedState,
+};
+
+/// Reconnection time used until the server provides one with a `retry` field.
+pub const DEFAULT_RETRY: Duration = Duration::from_secs(3);
+
+/// A storage of the last event ID, used to resume the stream across restarts.
+///
+/// The [`ReconnectingEventSource`] loads the last event ID before the first connection and saves
+/// it each time it changes, or clears it when the server resets it with an empty `id` field.
+pub trait LastEventIdStore: Send + Sync {
+    /// Loads the last event ID, if any.
+    fn load(&self) -> impl Future<Output = Option<String>> + Send;
+
+    /// Saves the last event ID. Overide previous value.
+    fn save(&self, last_event_id: &str) -> impl Future<Output = ()> + Send;
+
+    /// Clears the last event ID, so the next load finds none.
+    fn clear(&self) -> impl Future<Output = ()> + Send;
+}
+
+/// In-memory [`LastEventIdStore`], the default one.
+///
+/// Clones share the same value, so a clone kept aside can inspect what the stream saved.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    last_event_id: Arc<Mutex<Option<String>>>,
+}
+
+impl LastEventIdStore for MemoryStore {
+    async fn load(&self) -> Option<String> {
+        self.last_event_id
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    async fn save(&self, last_event_id: &str) {
+        *self
+            .last_event_id
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(last_event_id.to_string());
+    }
+
+    async fn clear(&self) {
+        *self
+            .last_event_id
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+    }
+}
+
+/// Builder of a [`Stream`] of [`Event`]s that reconnects when the connection is lost.
+pub struct ReconnectingEventSource<S = MemoryStore> {
+    request: RequestBuilder,
+    last_event_id: Option<String>,
+    retry: Duration,
+    store: S,
+    circuit_breaker: Option<CircuitBreaker>,
+    max_redirects: Option<usize>,
+    cancellation_token: Option<CancellationToken>,
+    dedup_window: Option<usize>,
+    config: EventSourceConfig,
+}
+
+impl ReconnectingEventSource {
+    /// Creates a new [`ReconnectingEventSource`] sending the given request on each connection.
+    ///
+    /// The request must be cloneable (see [`RequestBuilder::try_clone`]) to be sent again,
+    /// otherwise the stream ends with the first connection.
+    #[must_use]
+    pub fn new(request: RequestBuilder) -> Self {
+        Self {
+            request,
+            last_event_id: None,
+            retry: DEFAULT_RETRY,
+            store: MemoryStore::default(),
+            circuit_breaker: None,
+            max_redirects: None,
+            cancellation_token: None,
+            dedup_window: None,
+            config: EventSourceConfig::default(),
+        }
+    }
+}
+
+impl<S: LastEventIdStore + 'static> ReconnectingEventSource<S> {
+    /// Sets the `Last-Event-ID` sent on the first connection.
+    ///
+    /// The value also seeds the last event ID carried by the events and sent on reconnections,
+    /// until the server provides a new one. Takes precedence over the value loaded from the store.
+    #[must_use]
+    pub fn last_event_id(mut self, last_event_id: impl Into<String>) -> Self {
+        self.last_event_id = Some(last_event_id.into());
+        self
+    }
+
+    /// Sets the reconnection time used until the server provides one. Default to [`DEFAULT_RETRY`].
+    #[must_use]
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Sets the [`LastEventIdStore`] used to persist the last event ID.
+    ///
+    /// An event's ID is saved once the next item is requested, that is after the event has been
+    /// handled, so an interrupted run resumes from the last fully handled event.
+    #[must_use]
+    pub fn store<T: LastEventIdStore>(self, store: T) -> ReconnectingEventSource<T> {
+        ReconnectingEventSource {
+            request: self.request,
+            last_event_id: self.last_event_id,
+            retry: self.retry,
+            store,
+            circuit_breaker: self.circuit_breaker,
+            max_redirects: self.max_redirects,
+            cancellation_token: self.cancellation_token,
+            dedup_window: self.dedup_window,
+            config: self.config,
+        }
+    }
+
+    /// Sets the [`CircuitBreaker`] guarding connection attempts.
+    ///
+    /// While the circuit is open, the stream yields an [`EventError::CircuitOpen`] and waits for
+    /// the circuit to allow a new attempt.
+    #[must_use]
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Follows up to `max_redirects` redirections on each connection.
+    ///
+    /// Meant for clients with redirections disabled (see [`reqwest::redirect::Policy::none`]), as
+    /// the client would otherwise follow them before the source sees them. Permanent redirections
+    /// (`301` and `308`) are remembered: following connections go straight to the new location.
+    /// As for browsers, a `POST` request is switched to `GET` on `301` and `302`, and any request
+    /// but `HEAD` on `303`; the credentials are removed on redirections to another host. When the
+    /// limit is reached, the redirection is handled as any other non-`200` response.
+    #[must_use]
+    pub fn follow_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = Some(max_redirects);
+        self
+    }
+
+    /// Sets the [`CancellationToken`] ending the stream when cancelled, like
+    /// [`EventSourceConfig::cancellation_token`].
+    ///
+    /// The stream ends with `None`, whether it's waiting for events, for a connection, or for the
+    /// reconnection time.
+    #[must_use]
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Sets the [`EventSourceConfig`] applied to each connection.
+    ///
+    /// A connection failing with an [`EventError::StaleConnection`], an
+    /// [`EventError::IdleTimeout`], an [`EventError::FirstEventTimeout`] or an
+    /// [`EventError::HeartbeatMissed`] is dropped and re-established.
+    #[must_use]
+    pub fn config(mut self, config: EventSourceConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Skips the events whose ID is among the last `window` seen IDs.
+    ///
+    /// Only the events with their own `id` field are checked, not the ones carrying the ID of a
+    /// previous event, so events without ID are never skipped.
+    #[must_use]
+    pub fn deduplicate(mut self, window: usize) -> Self {
+        self.dedup_window = Some(window);
+        self
+    }
+
+    /// Connects to the event source and returns a faillable [`Stream`] of [`Event`]s, see
+    /// [`ReconnectingEvents`].
+    #[must_use]
+    pub fn events(self) -> ReconnectingEvents<S> {
+        let Self {
+            request,
+            last_event_id,
+            retry,
+            store,
+            circuit_breaker,
+            max_redirects,
+            cancellation_token,
+            dedup_window,
+            config,
+        } = self;
+        let store = Arc::new(store);
+        let state = if last_event_id.is_none() {
+            let store = Arc::clone(&store);
+            State::Loading(Box::pin(async move { store.load().await }))
+        } else {
+            State::Connect
+        };
+        let cancelled = cancellation_token
+            .or_else(|| config.cancellation_token.clone())
+            .map(|token| Box::pin(token.cancelled_owned()));
+        let deadline = config
+            .max_duration
+            .map(|duration| Box::pin(tokio::time::sleep(duration)));
+        ReconnectingEvents {
+            request: Some(request),
+            url: None,
+            last_event_id,
+            retry,
+            saver: Saver {
+                store,
+                saving: None,
+                unsaved: None,
+            },
+            circuit_breaker,
+            max_redirects,
+            seen_ids: dedup_window.map(SeenIds::new),
+            config,
+            stats: Arc::default(),
+            cancelled,
+            deadline,
+            state,
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("reconnecting_event_source"),
+        }
+    }
+}
+
+/// Future boxed to be kept across polls, as its type can't be named.
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Step of a [`ReconnectingEvents`] stream.
+enum State {
+    /// Loading the last event ID from the store, before the first connection.
+    Loading(BoxFuture<Option<String>>),
+    /// Waiting for the reconnection time, or for the circuit breaker to allow an attempt.
+    Waiting(Pin<Box<Sleep>>),
+    /// About to connect.
+    Connect,
+    /// Sending the request, with the URL to remember for the following connections.
+    Connecting(BoxFuture<(Result<Response, EventError>, Option<Url>)>),
+    /// Validating the response.
+    Opening(BoxFuture<Result<Parser, EventSourceError>>),
+    /// Reading the events of the response body.
+    Streaming(Box<Parser>),
+    /// The stream has ended.
+    Done,
+}
+
+/// Stream of the [`Event`]s of a [`ReconnectingEventSource`], connecting again each time the
+/// connection is lost.
+///
+/// Connection failures and I/O errors are yielded as [`EventError`]s, then the stream waits for
+/// the reconnection time and connects again. The stream ends after yielding an
+/// [`EventError::SourceError`] when the server rejects the connection, as the response isn't an
+/// event stream. It also ends, without error, on a `204 No Content` response, the server's way to
+/// tell the client to stop reconnecting.
+pub struct ReconnectingEvents<S = MemoryStore> {
+    /// The request sent on each connection, taken by the last attempt when it can't be cloned.
+    request: Option<RequestBuilder>,
+    /// The URL of the last permanent redirection, overriding the request one.
+    url: Option<Url>,
+    last_event_id: Option<String>,
+    retry: Duration,
+    /// Saves the IDs of the yielded events, completed before reading the next event, or ending
+    /// the stream.
+    saver: Saver<S>,
+    circuit_breaker: Option<CircuitBreaker>,
+    max_redirects: Option<usize>,
+    seen_ids: Option<SeenIds>,
+    config: EventSourceConfig,
+    /// Stats of all the connections.
+    stats: Arc<Stats>,
+    /// Completed once the cancellation token is cancelled, if any.
+    cancelled: Option<Pin<Box<WaitForCancellationFutureOwned>>>,
+    /// Deadline of [`EventSourceConfig::max_duration`], covering all the connections.
+    deadline: Option<Pin<Box<Sleep>>>,
+    state: State,
+    /// Span of the source, entered while polling, so it's the parent of the connections' ones.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl<S> ReconnectingEvents<S> {
+    /// Returns a snapshot of the [`StreamStats`] of all the connections, without locking.
+    #[must_use]
+    pub fn stats(&self) -> StreamStats {
+        self.stats.snapshot()
+    }
+
+    /// Returns the statistics of the events of all the connections by type, see
+    /// [`EventStream::event_type_stats`](crate::stream::EventStream::event_type_stats).
+    #[must_use]
+    pub fn event_type_stats(&self) -> HashMap<String, EventTypeStats> {
+        self.stats.event_type_stats()
+    }
+}
+
+impl<S: LastEventIdStore + 'static> ReconnectingEvents<S> {
+    /// Returns the request of the next attempt, cloning the original one as long as possible, so
+    /// it can be sent again.
+    fn attempt(&mut self) -> Option<RequestBuilder> {
+        let attempt = match self.request.as_ref().and_then(RequestBuilder::try_clone) {
+            Some(attempt) => attempt,
+            None => self.request.take()?,
+        };
+        Some(match &self.last_event_id {
+            Some(last_event_id) => attempt.header(&LAST_EVENT_ID, last_event_id),
+            None => attempt,
+        })
+    }
+
+    /// Sends the request, following the redirections and applying the connection timeout as
+    /// configured.
+    fn send(&mut self, attempt: RequestBuilder) -> State {
+        let mut url = self.url.take();
+        let max_redirects = self.max_redirects;
+        let connect_timeout = self.config.connect_timeout;
+        State::Connecting(Box::pin(async move {
+            let send = async {
+                match max_redirects {
+                    Some(max_redirects) => {
+                        send_following_redirects(attempt, &mut url, max_redirects).await
+                    }
+                    None => attempt.send().await,
+                }
+            };
+            let response = match connect_timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, send).await {
+                    Ok(response) => response.map_err(EventError::RequestError),
+                    Err(_) => Err(EventError::ConnectTimeout),
+                },
+                None => send.await.map_err(EventError::RequestError),
+            };
+            (response, url)
+        }))
+    }
+
+    /// Returns the configuration of the next connection, with the time left of the maximum
+    /// duration, if any.
+    fn connection_config(&self) -> EventSourceConfig {
+        let mut config = self.config.clone();
+        if let Some(deadline) = &self.deadline {
+            config.max_duration = Some(
+                deadline
+                    .deadline()
+                    .saturating_duration_since(tokio::time::Instant::now()),
+            );
+        }
+        config
+    }
+
+    /// Handles an event read from the current connection, returning `None` if it's a duplicate.
+    fn handle_event(&mut self, event: Event, has_id: bool) -> Option<Event> {
+        if has_id
+            && let (Some(seen_ids), Some(id)) = (&mut self.seen_ids, &event.last_event_id)
+            && !seen_ids.insert(id)
+        {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(id, "duplicate event skipped");
+            return None;
+        }
+        if event.last_event_id != self.last_event_id {
+            self.saver.unsaved = Some(match event.last_event_id.clone() {
+                Some(id) => IdUpdate::Save(id),
+                None => IdUpdate::Clear,
+            });
+        }
+        self.last_event_id.clone_from(&event.last_event_id);
+        if let Some(retry) = event.retry {
+            self.retry = retry;
+        }
+        Some(event)
+    }
+}
+
+impl<S: LastEventIdStore + 'static> Stream for ReconnectingEvents<S> {
+    type Item = Result<Event, EventError>;
+
+    #[allow(clippy::too_many_lines)]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        #[cfg(feature = "tracing")]
+        let _entered = this.span.clone().entered();
+        if let Some(cancelled) = &mut this.cancelled
+            && cancelled.as_mut().poll(cx).is_ready()
+        {
+            #[cfg(feature = "tracing")]
+            if !matches!(this.state, State::Done) {
+                tracing::debug!("reconnecting event source cancelled");
+            }
+            this.state = State::Done;
+        }
+        // the current connection ends by itself, after the events already received
+        if let Some(deadline) = &mut this.deadline
+            && !matches!(this.state, State::Streaming(_) | State::Done)
+            && deadline.as_mut().poll(cx).is_ready()
+        {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("maximum duration of reconnecting event source elapsed");
+            this.state = State::Done;
+        }
+        loop {
+            match &mut this.state {
+                State::Loading(load) => {
+                    this.last_event_id = ready!(load.as_mut().poll(cx));
+                    this.state = State::Connect;
+                }
+                State::Waiting(sleep) => {
+                    let _ = this.saver.poll(cx);
+                    ready!(sleep.as_mut().poll(cx));
+                    this.stats.record_reconnect();
+                    this.state = State::Connect;
+                }
+                State::Connect => {
+                    if let Some(circuit_breaker) = &this.circuit_breaker
+                        && let Err((state, wait)) = circuit_breaker.try_acquire()
+                    {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(?state, ?wait, "circuit open, connection delayed");
+                        this.state = State::Waiting(Box::pin(tokio::time::sleep(wait)));
+                        return Poll::Ready(Some(Err(EventError::CircuitOpen(state))));
+                    }
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        last_event_id = this.last_event_id.as_deref(),
+                        "connecting to event source"
+                    );
+                    this.state = match this.attempt() {
+                        Some(attempt) => this.send(attempt),
+                        None => State::Done,
+                    };
+                }
+                State::Connecting(send) => {
+                    let (response, url) = ready!(send.as_mut().poll(cx));
+                    this.url = url;
+                    let response = match response {
+                        Ok(response) => response,
+                        Err(error) => {
+                            if let Some(circuit_breaker) = &this.circuit_breaker {
+                                circuit_breaker.record_failure();
+                            }
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(%error, retry = ?this.retry, "connection failed");
+                            this.state = State::Waiting(Box::pin(tokio::time::sleep(this.retry)));
+                            return Poll::Ready(Some(Err(error)));
+                        }
+                    };
+                    this.state = if response.status() == StatusCode::NO_CONTENT {
+                        if let Some(circuit_breaker) = &this.circuit_breaker {
+                            circuit_breaker.record_success();
+                        }
+                        #[cfg(feature = "tracing")]
+                        tracing::info!("event source asked not to reconnect");
+                        State::Done
+                    } else {
+                        State::Opening(Box::pin(parse_response(
+                            response,
+                            SharedState::with_stats(
+                                this.last_event_id.clone(),
+                                Arc::clone(&this.stats),
+                            ),
+                            this.connection_config(),
+                            Mode::Events,
+                        )))
+                    };
+                }
+                State::Opening(open) => match ready!(open.as_mut().poll(cx)) {
+                    Ok(events) => {
+                        if let Some(circuit_breaker) = &this.circuit_breaker {
+                            circuit_breaker.record_success();
+                        }
+                        this.state = State::Streaming(Box::new(events));
+                    }
+                    Err(error) => {
+                        // a bad status, content type or a rejection by the validator is a failure
+                        if let Some(circuit_breaker) = &this.circuit_breaker {
+                            circuit_breaker.record_failure();
+                        }
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(%error, "event source rejected the connection");
+                        this.state = State::Done;
+                        return Poll::Ready(Some(Err(EventError::SourceError(error))));
+                    }
+                },
+                State::Streaming(events) => {
+                    ready!(this.saver.poll(cx));
+                    let (event, has_id) = match ready!(Pin::new(&mut **events).poll_next(cx)) {
+                        Some(Ok(Parsed::Event(event, has_id))) => (event, has_id),
+                        Some(Ok(_)) => continue,
+                        Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                        None if this
+                            .deadline
+                            .as_ref()
+                            .is_some_and(|deadline| deadline.is_elapsed()) =>
+                        {
+                            this.state = State::Done;
+                            continue;
+                        }
+                        None => {
+                            #[cfg(feature = "tracing")]
+                            tracing::info!(retry = ?this.retry, "connection lost, reconnecting");
+                            this.state = State::Waiting(Box::pin(tokio::time::sleep(this.retry)));
+                            continue;
+                        }
+                    };
+                    if let Some(event) = this.handle_event(event, has_id) {
+                        return Poll::Ready(Some(Ok(event)));
+                    }
+                }
+                State::Done => {
+                    // the last event ID is saved before ending, e.g. once cancelled
+                    ready!(this.saver.poll(cx));
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
+}
+
+/// Saver of the event IDs to a [`LastEventIdStore`].
+struct Saver<S> {
+    store: Arc<S>,
+    /// Saving in progress, never interrupted, as stores may not support it.
+    saving: Option<BoxFuture<()>>,
+    /// ID of the last yielded event, saved once the saving in progress completes, so only the last
+    /// of the IDs yielded meanwhile is saved.
+    unsaved: Option<IdUpdate>,
+}
+
+/// Update of the last event ID to apply to the store.
+enum IdUpdate {
+    /// The ID to save.
+    Save(String),
+    /// The ID was reset by the server, with an empty `id` field.
+    Clear,
+}
+
+impl<S: LastEventIdStore + 'static> Saver<S> {
+    /// Drives the saving of the event IDs, completing once the last one is saved.
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            if let Some(saving) = &mut self.saving {
+                ready!(saving.as_mut().poll(cx));
+                self.saving = None;
+            }
+            let Some(update) = self.unsaved.take() else {
+                return Poll::Ready(());
+            };
+            let store = Arc::clone(&self.store);
+            self.saving = Some(Box::pin(async move {
+                match update {
+                    IdUpdate::Save(id) => store.save(&id).await,
+                    IdUpdate::Clear => store.clear().await,
+                }
+            }));
+        }
+    }
+}
+
+/// Sliding window of the last seen event IDs.
+struct SeenIds {
+    window: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl SeenIds {
+    fn new(window: usize) -> Self {
+        Self {
+            window,
+            order: VecDeque::with_capacity(window),
+            ids: HashSet::with_capacity(window),
+        }
+    }
+
+    /// Records the ID, forgetting the oldest one if the window is full.
+    ///
+    /// Returns whether the ID is new.
+    fn insert(&mut self, id: &str) -> bool {
+        if self.ids.contains(id) {
+            return false;
+        }
+        if self.window == 0 {
+            return true;
+        }
+        if self.order.len() == self.window
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+        self.order.push_back(id.to_string());
+        self.ids.insert(id.to_string());
+        true
+    }
+}
+
+/// Sends the request, following up to `max_redirects` redirections.
+///
+/// `url` overrides the request URL. It's updated when all the followed redirections are
+/// permanent.
+async fn send_following_redirects(
+    attempt: RequestBuilder,
+    url: &mut Option<Url>,
+    max_redirects: usize,
+) -> Result<Response, reqwest::Error> {
+    let (client, request) = attempt.build_split();
+    let mut request = request?;
+    if let Some(url) = url {
+        redirect(&mut request, url.clone());
+    }
+
+    let mut permanent = true;
+    for _ in 0..max_redirects {
+        let next = request.try_clone();
+        let response = client.execute(request).await?;
+
+        let status = response.status();
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| response.url().join(location).ok());
+        let (Some(mut next), Some(location)) = (next, location) else {
+            return Ok(response);
+        };
+        match status {
+            StatusCode::MOVED_PERMANENTLY => switch_post_to_get(&mut next),
+            StatusCode::PERMANENT_REDIRECT => {}
+            StatusCode::FOUND => {
+                permanent = false;
+                switch_post_to_get(&mut next);
+            }
+            StatusCode::TEMPORARY_REDIRECT => permanent = false,
+            StatusCode::SEE_OTHER => {
+                permanent = false;
+                if next.method() != Method::HEAD {
+                    into_get(&mut next);
+                }
+            }
+            _ => return Ok(response),
+        }
+
+        if permanent {
+            *url = Some(location.clone());
+        }
+        redirect(&mut next, location);
+        request = next;
+    }
+
+    client.execute(request).await
+}
+
+/// Switches a `POST` request to `GET`, as browsers do on `301` and `302` redirections.
+fn switch_post_to_get(request: &mut Request) {
+    if request.method() == Method::POST {
+        into_get(request);
+    }
+}
+
+/// Turns the request into a `GET` one, dropping its body and the headers describing it.
+fn into_get(request: &mut Request) {
+    *request.method_mut() = Method::GET;
+    *request.body_mut() = None;
+    let headers = request.headers_mut();
+    for header in [
+        CONTENT_TYPE,
+        CONTENT_LENGTH,
+        CONTENT_ENCODING,
+        TRANSFER_ENCODING,
+    ] {
+        headers.remove(header);
+    }
+}
+
+/// Points the request to a new URL, removing credentials when the host changes.
+fn redirect(request: &mut Request, url: Url) {
+    let cross_host = url.host_str() != request.url().host_str()
+        || url.port_or_known_default() != request.url().port_or_known_default();
+    if cross_host {
+        let headers = request.headers_mut();
+        headers.remove(AUTHORIZATION);
+        headers.remove(COOKIE);
+        headers.remove(PROXY_AUTHORIZATION);
+    }
+    *request.url_mut() = url;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_an_unpin_stream() {
+        fn assert_stream<T: Stream + Send + Unpin>(_: &T) {}
+
+        let request = reqwest::Client::new().get("http://localhost");
+        let events: ReconnectingEvents = ReconnectingEventSource::new(request).events();
+        assert_stream(&events);
+    }
+
+    #[test]
+    fn forget_oldest_seen_ids() {
+        let mut seen_ids = SeenIds::new(2);
+        assert!(seen_ids.insert("1"));
+        assert!(seen_ids.insert("2"));
+        assert!(!seen_ids.insert("1"));
+        assert!(seen_ids.insert("3"));
+        assert!(seen_ids.insert("1"));
+        assert!(!seen_ids.insert("3"));
+    }
+}
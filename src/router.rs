@@ -0,0 +1,214 @@
+//! Dispatching events to handlers registered by event type.
+//!
+//! An [`EventRouter`] drives a stream of events, calling the handler registered for the type of
+//! each event, or the fallback one, instead of matching on the type by hand.
+//!
+//! ```rust,no_run
+//! use reqwest_sse::{EventSource, router::EventRouter};
+//!
+//! # async fn run() {
+//! let events = reqwest::get("https://sse.test-free.online/api/story")
+//!     .await.unwrap()
+//!     .events()
+//!     .await.unwrap();
+//!
+//! let result = EventRouter::<std::io::Error>::new()
+//!     .route("story", |event| async move {
+//!         println!("story: {}", event.data);
+//!         Ok(())
+//!     })
+//!     .fallback(|event| async move {
+//!         println!("{}: {}", event.event_type, event.data);
+//!         Ok(())
+//!     })
+//!     .run(events)
+//!     .await;
+//! # }
+//! ```
+use std::{collections::HashMap, pin::Pin};
+
+use futures_core::Stream;
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use crate::{
+    Event,
+    error::{EventError, SubscribeError},
+};
+
+type Handler<E> =
+    Box<dyn FnMut(Event) -> Pin<Box<dyn Future<Output = Result<(), E>> + Send>> + Send>;
+
+fn boxed<F, Fut, E>(mut handler: F) -> Handler<E>
+where
+    F: FnMut(Event) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+{
+    Box::new(move |event| Box::pin(handler(event)))
+}
+
+/// Handlers of events, by event type.
+///
+/// Events of a type without handler go to the fallback handler, or are ignored if there is none.
+/// The handlers are called one event at a time, in the order of the stream.
+pub struct EventRouter<E> {
+    routes: HashMap<String, Handler<E>>,
+    fallback: Option<Handler<E>>,
+}
+
+impl<E> Default for EventRouter<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> EventRouter<E> {
+    /// Creates a router without handlers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            routes: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Registers the handler of the events of the type, replacing the previous one.
+    #[must_use]
+    pub fn route<F, Fut>(mut self, event_type: impl Into<String>, handler: F) -> Self
+    where
+        F: FnMut(Event) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+    {
+        self.routes.insert(event_type.into(), boxed(handler));
+        self
+    }
+
+    /// Registers the handler of the events of the types without handler.
+    #[must_use]
+    pub fn fallback<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: FnMut(Event) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+    {
+        self.fallback = Some(boxed(handler));
+        self
+    }
+
+    /// Drives the stream, dispatching each event to its handler.
+    ///
+    /// Ends with the stream, or as soon as the stream yields an error or a handler fails,
+    /// reporting why with a [`SubscribeError`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`SubscribeError`] ending the stream.
+    pub async fn run<S>(mut self, mut stream: S) -> Result<(), SubscribeError<E>>
+    where
+        S: Stream<Item = Result<Event, EventError>> + Unpin,
+    {
+        while let Some(result) = stream.next().await {
+            let event = result.map_err(SubscribeError::EventError)?;
+            let handler = match self.routes.get_mut(&*event.event_type) {
+                Some(handler) => handler,
+                None => match &mut self.fallback {
+                    Some(handler) => handler,
+                    None => continue,
+                },
+            };
+            handler(event).await.map_err(SubscribeError::HandlerError)?;
+        }
+        Ok(())
+    }
+
+    /// Spawns a task running the router on the stream, see [`EventRouter::run`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn spawn<S>(self, stream: S) -> JoinHandle<Result<(), SubscribeError<E>>>
+    where
+        S: Stream<Item = Result<Event, EventError>> + Unpin + Send + 'static,
+        E: Send + 'static,
+    {
+        crate::spawn("reqwest-sse router", self.run(stream))
+    }
+}
+
+impl<E> std::fmt::Debug for EventRouter<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventRouter")
+            .field("routes", &self.routes.keys().collect::<Vec<_>>())
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::event_of_type as event;
+
+    fn recorder(
+        log: &Arc<Mutex<Vec<String>>>,
+        name: &'static str,
+    ) -> impl FnMut(Event) -> std::future::Ready<Result<(), String>> + use<> {
+        let log = log.clone();
+        move |event| {
+            log.lock().unwrap().push(format!("{name}: {}", event.data));
+            std::future::ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatch_events_by_type() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let events = tokio_stream::iter(vec![
+            Ok(event("add", "1")),
+            Ok(event("remove", "2")),
+            Ok(event("message", "3")),
+            Ok(event("add", "4")),
+        ]);
+
+        EventRouter::new()
+            .route("add", recorder(&log, "add"))
+            .route("remove", recorder(&log, "remove"))
+            .fallback(recorder(&log, "other"))
+            .run(events)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["add: 1", "remove: 2", "other: 3", "add: 4"]
+        );
+    }
+
+    #[tokio::test]
+    async fn ignore_unrouted_events_without_fallback() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let events = tokio_stream::iter(vec![Ok(event("message", "1")), Ok(event("add", "2"))]);
+
+        EventRouter::new()
+            .route("add", recorder(&log, "add"))
+            .run(events)
+            .await
+            .unwrap();
+
+        assert_eq!(*log.lock().unwrap(), ["add: 2"]);
+    }
+
+    #[tokio::test]
+    async fn stop_at_handler_error() {
+        let events = tokio_stream::iter(vec![Ok(event("add", "1")), Ok(event("add", "2"))]);
+
+        let result = EventRouter::new()
+            .route("add", |event: Event| async move { Err(event.data) })
+            .spawn(events)
+            .await
+            .unwrap();
+
+        assert!(matches!(result, Err(SubscribeError::HandlerError(data)) if data == "1"));
+    }
+}
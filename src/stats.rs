@@ -0,0 +1,201 @@
+//! Statistics of event streams, e.g. for health checks.
+//!
+//! [`EventStream::stats`](crate::stream::EventStream::stats) and
+//! [`ReconnectingEvents::stats`](crate::reconnect::ReconnectingEvents::stats) return a
+//! [`StreamStats`] snapshot. The counters are atomics updated as the stream is read, so taking a
+//! snapshot never waits for the stream.
+//!
+//! The events are also counted by type, e.g. to detect when a server stops sending one of them,
+//! see [`EventStream::event_type_stats`](crate::stream::EventStream::event_type_stats).
+//!
+//! ```rust,no_run
+//! use tokio_stream::StreamExt;
+//!
+//! use reqwest_sse::EventSource;
+//!
+//! # async fn run() {
+//! let mut events = reqwest::get("https://sse.test-free.online/api/story")
+//!     .await.unwrap()
+//!     .events()
+//!     .await.unwrap();
+//!
+//! while let Some(Ok(_)) = events.next().await {
+//!     let stats = events.stats();
+//!     println!("{} events, {} bytes", stats.events_received, stats.bytes_consumed);
+//! }
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime},
+};
+
+/// Maximum number of event types counted by type, beyond which the events of new types are only
+/// counted in [`StreamStats::events_received`], so that a stream of ever-changing types doesn't
+/// grow the counters.
+pub const MAX_COUNTED_EVENT_TYPES: usize = 64;
+
+/// Snapshot of the statistics of a stream.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct StreamStats {
+    /// Number of events received.
+    pub events_received: u64,
+    /// Number of bytes read from the response bodies.
+    pub bytes_consumed: u64,
+    /// Time the current connection was established, i.e. its response accepted.
+    pub connected_at: Option<SystemTime>,
+    /// Time the last event was received.
+    pub last_event_at: Option<SystemTime>,
+    /// Number of reconnection attempts, always 0 for a stream of a single response.
+    pub reconnects: u64,
+}
+
+/// Statistics of the events of a given type.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct EventTypeStats {
+    /// Number of events received.
+    pub events_received: u64,
+    /// Time the last event was received.
+    pub last_event_at: SystemTime,
+}
+
+/// Counters of a stream, shared between the parser and the stream handles.
+#[derive(Debug, Default)]
+pub(crate) struct Stats {
+    events_received: AtomicU64,
+    bytes_consumed: AtomicU64,
+    /// Nanoseconds since the Unix epoch, 0 when unset.
+    connected_at: AtomicU64,
+    /// Nanoseconds since the Unix epoch, 0 when unset.
+    last_event_at: AtomicU64,
+    reconnects: AtomicU64,
+    /// Counters by event type, looked up by pointer first, as the parser interns the types.
+    event_types: Mutex<Vec<(Arc<str>, EventTypeStats)>>,
+}
+
+impl Stats {
+    pub(crate) fn record_event(&self, event_type: &Arc<str>) {
+        self.events_received.fetch_add(1, Ordering::Relaxed);
+        let now = now();
+        self.last_event_at.store(now, Ordering::Relaxed);
+
+        let last_event_at = time(now).unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut event_types = self
+            .event_types
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some((_, stats)) = event_types
+            .iter_mut()
+            .find(|(counted, _)| Arc::ptr_eq(counted, event_type) || counted == event_type)
+        {
+            stats.events_received += 1;
+            stats.last_event_at = last_event_at;
+        } else if event_types.len() < MAX_COUNTED_EVENT_TYPES {
+            event_types.push((
+                Arc::clone(event_type),
+                EventTypeStats {
+                    events_received: 1,
+                    last_event_at,
+                },
+            ));
+        }
+    }
+
+    pub(crate) fn record_bytes(&self, bytes: usize) {
+        self.bytes_consumed
+            .fetch_add(u64::try_from(bytes).unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_connection(&self) {
+        self.connected_at.store(now(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> StreamStats {
+        StreamStats {
+            events_received: self.events_received.load(Ordering::Relaxed),
+            bytes_consumed: self.bytes_consumed.load(Ordering::Relaxed),
+            connected_at: time(self.connected_at.load(Ordering::Relaxed)),
+            last_event_at: time(self.last_event_at.load(Ordering::Relaxed)),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn event_type_stats(&self) -> HashMap<String, EventTypeStats> {
+        self.event_types
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(event_type, stats)| (event_type.to_string(), *stats))
+            .collect()
+    }
+}
+
+/// Returns the current time, in nanoseconds since the Unix epoch.
+fn now() -> u64 {
+    let elapsed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Converts a time in nanoseconds since the Unix epoch, 0 meaning none.
+fn time(nanos: u64) -> Option<SystemTime> {
+    (nanos != 0).then(|| SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_counters() {
+        let stats = Stats::default();
+        assert_eq!(stats.snapshot(), StreamStats::default());
+
+        let before = SystemTime::now();
+        stats.record_connection();
+        stats.record_bytes(12);
+        let update = Arc::from("update");
+        stats.record_event(&update);
+        stats.record_event(&Arc::from("message"));
+        stats.record_event(&update);
+        stats.record_reconnect();
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.events_received, 3);
+        assert_eq!(snapshot.bytes_consumed, 12);
+        assert_eq!(snapshot.reconnects, 1);
+        assert!(snapshot.connected_at.unwrap() >= before);
+        assert!(snapshot.last_event_at.unwrap() >= snapshot.connected_at.unwrap());
+
+        let event_types = stats.event_type_stats();
+        assert_eq!(event_types.len(), 2);
+        assert_eq!(event_types["update"].events_received, 2);
+        assert_eq!(
+            event_types["update"].last_event_at,
+            snapshot.last_event_at.unwrap()
+        );
+        assert_eq!(event_types["message"].events_received, 1);
+    }
+
+    #[test]
+    fn bound_counted_event_types() {
+        let stats = Stats::default();
+        for index in 0..=MAX_COUNTED_EVENT_TYPES {
+            stats.record_event(&Arc::from(format!("type-{index}")));
+        }
+        assert_eq!(
+            stats.snapshot().events_received,
+            u64::try_from(MAX_COUNTED_EVENT_TYPES + 1).unwrap()
+        );
+        assert_eq!(stats.event_type_stats().len(), MAX_COUNTED_EVENT_TYPES);
+    }
+}
@@ -0,0 +1,900 @@
+//! Stream of events returned by [`EventSource`](crate::EventSource).
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_core::{FusedStream, Stream};
+use reqwest::{Response, StatusCode, Url, header::HeaderMap};
+use tokio::{
+    io::{AsyncBufRead, AsyncRead, ReadBuf},
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    BodyReader, Event, Mode, Parsed, Parser, body_reader,
+    config::EventSourceConfig,
+    error::{EventError, SubscribeError},
+    stats::{EventTypeStats, Stats, StreamStats},
+};
+
+/// Metadata of the response an [`EventStream`] is read from.
+#[derive(Debug)]
+pub(crate) struct Metadata {
+    status: StatusCode,
+    headers: HeaderMap,
+    url: Url,
+}
+
+impl Metadata {
+    /// Captures the metadata of the response, before its body is consumed.
+    pub(crate) fn of(response: &Response) -> Self {
+        Self {
+            status: response.status(),
+            headers: response.headers().clone(),
+            url: response.url().clone(),
+        }
+    }
+
+    /// Metadata of a stream not read from a response: `200 OK`, without headers, from
+    /// `about:blank`.
+    pub(crate) fn detached() -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            url: Url::parse("about:blank").expect("valid URL"),
+        }
+    }
+}
+
+/// State of the stream, updated by the parser as the lines are read.
+#[derive(Debug, Default)]
+pub(crate) struct StreamState {
+    /// The last event ID, as set on each dispatch, even without event.
+    pub(crate) last_event_id: Option<String>,
+    /// The last reconnection time sent by the server.
+    pub(crate) retry: Option<Duration>,
+}
+
+/// [`StreamState`] shared between the parser and the [`EventStream`], with the [`Stats`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedState {
+    state: Arc<Mutex<StreamState>>,
+    stats: Arc<Stats>,
+}
+
+impl SharedState {
+    /// Creates a state with the given last event ID.
+    pub(crate) fn new(last_event_id: Option<String>) -> Self {
+        Self::with_stats(last_event_id, Arc::default())
+    }
+
+    /// Creates a state with the given last event ID, updating the given stats, e.g. the ones of
+    /// all the connections of a reconnecting source.
+    pub(crate) fn with_stats(last_event_id: Option<String>, stats: Arc<Stats>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(StreamState {
+                last_event_id,
+                retry: None,
+            })),
+            stats,
+        }
+    }
+
+    /// Locks the state, which can't be left inconsistent by a panic.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, StreamState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the stats of the stream.
+    pub(crate) fn stats(&self) -> &Arc<Stats> {
+        &self.stats
+    }
+}
+
+/// Stream of the events of a [`Response`], keeping its metadata.
+///
+/// Its items are [`Event`]s by default, [`EventOrComment`](crate::EventOrComment)s for
+/// [`EventSource::events_with_comments`](crate::EventSource::events_with_comments), or the
+/// lower-level items of [`EventSource::sse_lines`](crate::EventSource::sse_lines) and
+/// [`EventSource::fields`](crate::EventSource::fields). Being a named type, it can be stored in a
+/// struct field or returned by a function as is.
+///
+/// The status, headers and URL of the response remain available once its body is consumed as a
+/// stream, e.g. to log a request ID header.
+///
+/// It's `Send + 'static`, so it can be moved to a task spawned with `tokio::spawn`, and [`Unpin`],
+/// so [`StreamExt::next`] can be called without pinning it first.
+pub struct EventStream<T = Event> {
+    /// The parser of the response body, dropped once ended or closed.
+    parser: Option<Parser>,
+    /// Converts the parsed items into the items of the stream, skipping the unwanted ones.
+    convert: fn(Parsed) -> Option<T>,
+    /// The event lent by [`EventStream::next_ref`], recycled by the next call.
+    lent: Option<Event>,
+    metadata: Metadata,
+    state: SharedState,
+}
+
+impl<T> EventStream<T> {
+    /// Wraps the parser of the response body, with the response metadata.
+    pub(crate) fn new(
+        metadata: Metadata,
+        state: SharedState,
+        parser: Parser,
+        convert: fn(Parsed) -> Option<T>,
+    ) -> Self {
+        Self {
+            parser: Some(parser),
+            convert,
+            lent: None,
+            metadata,
+            state,
+        }
+    }
+
+    /// Closes the stream, dropping the connection. Following polls return `None`.
+    ///
+    /// Like the browser `EventSource.close()`, it's an explicit alternative to dropping the stream.
+    pub fn close(&mut self) {
+        self.parser = None;
+    }
+
+    /// Stops parsing events, and returns the rest of the response body, e.g. for protocols
+    /// switching from SSE framing to raw bytes mid-response.
+    ///
+    /// The body resumes right after the last complete line read by the parser, including the
+    /// bytes it has already buffered, and those of a line being read. Lines of an event not
+    /// dispatched yet are lost. Use [`ReaderStream`] to read it as a stream of bytes.
+    ///
+    /// Returns `None` if the stream has ended or has been closed.
+    #[must_use]
+    pub fn into_inner(self) -> Option<Remaining> {
+        let (reader, pending, skip_lf) = self.parser?.into_parts();
+        Some(Remaining {
+            pending,
+            position: 0,
+            reader,
+            skip_lf,
+        })
+    }
+
+    /// Returns the status of the response, `200 OK` for a stream not read from a response, e.g.
+    /// created with [`EventStream::from_byte_stream`].
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        self.metadata.status
+    }
+
+    /// Returns the headers of the response, none for a stream not read from a response.
+    #[must_use]
+    pub fn headers(&self) -> &HeaderMap {
+        &self.metadata.headers
+    }
+
+    /// Returns the final URL of the response, after redirections, `about:blank` for a stream not
+    /// read from a response.
+    #[must_use]
+    pub fn url(&self) -> &Url {
+        &self.metadata.url
+    }
+
+    /// Returns the last event ID of the stream, to send back in the `Last-Event-ID` header.
+    ///
+    /// As for the browser `EventSource`, it's updated at the end of each event block, and kept by
+    /// the following blocks without `id` field, even those dispatching no event. An empty `id`
+    /// field resets it.
+    #[must_use]
+    pub fn last_event_id(&self) -> Option<String> {
+        self.state.lock().last_event_id.clone()
+    }
+
+    /// Returns the last reconnection time sent by the server with a `retry` field, if any.
+    ///
+    /// It's updated as soon as the field is read, even if its event is never dispatched.
+    #[must_use]
+    pub fn retry(&self) -> Option<Duration> {
+        self.state.lock().retry
+    }
+
+    /// Returns a snapshot of the [`StreamStats`], without locking.
+    #[must_use]
+    pub fn stats(&self) -> StreamStats {
+        self.state.stats().snapshot()
+    }
+
+    /// Returns the statistics of the events by type, up to
+    /// [`MAX_COUNTED_EVENT_TYPES`](crate::stats::MAX_COUNTED_EVENT_TYPES) types.
+    ///
+    /// Unlike [`EventStream::stats`], it briefly locks the counters, also locked by the parser on
+    /// each event.
+    #[must_use]
+    pub fn event_type_stats(&self) -> HashMap<String, EventTypeStats> {
+        self.state.stats().event_type_stats()
+    }
+
+    /// Waits for the next item, then returns it with the following ones ready without waiting,
+    /// up to `max` items, e.g. all the events of a chunk received at once.
+    ///
+    /// Returns `None` once the stream has ended.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is `0`.
+    pub async fn next_batch(&mut self, max: usize) -> Option<Vec<Result<T, EventError>>> {
+        assert!(max > 0, "batch size must not be zero");
+        std::future::poll_fn(|cx| {
+            let mut batch = Vec::new();
+            while batch.len() < max {
+                match Pin::new(&mut *self).poll_next(cx) {
+                    Poll::Ready(Some(item)) => batch.push(item),
+                    Poll::Pending if batch.is_empty() => return Poll::Pending,
+                    Poll::Ready(None) | Poll::Pending => break,
+                }
+            }
+            Poll::Ready(Some(batch).filter(|batch| !batch.is_empty()))
+        })
+        .await
+    }
+}
+
+impl<T: Send + 'static> EventStream<T> {
+    /// Spawns a task driving the stream, and returns the receiving half of a channel fed with its
+    /// items, able to buffer `capacity` items.
+    ///
+    /// The task reads ahead until the channel is full, plus the item waiting for room, then reads
+    /// no more until items are received. It ends with the stream or once the receiver is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`, or if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn into_channel(mut self, capacity: usize) -> mpsc::Receiver<Result<T, EventError>> {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let name = format!("reqwest-sse channel {}", self.url());
+        crate::spawn(&name, async move {
+            while let Some(result) = self.next().await {
+                if sender.send(result).await.is_err() {
+                    // nobody is listening anymore
+                    return;
+                }
+            }
+        });
+        receiver
+    }
+}
+
+impl<T: Clone + Send + 'static> EventStream<T> {
+    /// Prepares to fan the items of the stream out to many receivers, through a
+    /// [`broadcast`] channel able to buffer `capacity` items.
+    ///
+    /// Errors are shared between the receivers with an [`Arc`], as they can't be cloned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    #[must_use]
+    pub fn broadcast(self, capacity: usize) -> Broadcast<T> {
+        let (sender, _) = broadcast::channel(capacity);
+        Broadcast {
+            stream: self,
+            sender,
+        }
+    }
+}
+
+/// Item of a [`Broadcast`] channel.
+pub type BroadcastItem<T> = Result<T, Arc<EventError>>;
+
+/// An [`EventStream`] about to be fanned out to many receivers, returned by
+/// [`EventStream::broadcast`].
+///
+/// Receivers are created with [`Broadcast::subscribe`] before the first item is sent with
+/// [`Broadcast::start`], so none of them misses it. A receiver falling behind by more than the
+/// channel capacity skips the oldest items, and is told how many with
+/// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged).
+#[derive(Debug)]
+pub struct Broadcast<T> {
+    stream: EventStream<T>,
+    sender: broadcast::Sender<BroadcastItem<T>>,
+}
+
+impl<T: Clone + Send + 'static> Broadcast<T> {
+    /// Creates a new receiver of the items.
+    ///
+    /// Receivers can still be created once started, with
+    /// [`Receiver::resubscribe`](broadcast::Receiver::resubscribe), but only get the items sent
+    /// afterward.
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<BroadcastItem<T>> {
+        self.sender.subscribe()
+    }
+
+    /// Spawns a task driving the stream and sending its items to the receivers.
+    ///
+    /// The task ends with the stream, or once all the receivers are dropped. The receivers are then
+    /// told the channel is closed, after receiving the remaining items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn start(self) -> JoinHandle<()> {
+        let Self { mut stream, sender } = self;
+        let name = format!("reqwest-sse broadcast {}", stream.url());
+        crate::spawn(&name, async move {
+            while let Some(result) = stream.next().await {
+                if sender.send(result.map_err(Arc::new)).is_err() {
+                    // nobody is listening anymore
+                    return;
+                }
+            }
+        })
+    }
+}
+
+impl EventStream {
+    /// Creates a stream of the events read from a stream of bytes, e.g. a body from another HTTP
+    /// client, a file, or a message queue, with the default [`EventSourceConfig`].
+    ///
+    /// The bytes are parsed as the body of an accepted response: there's no status nor content
+    /// type to check.
+    ///
+    /// ```rust
+    /// use bytes::Bytes;
+    /// use tokio_stream::StreamExt;
+    ///
+    /// use reqwest_sse::stream::EventStream;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let chunks = [Ok::<_, std::io::Error>(Bytes::from("data: hello\n\n"))];
+    /// let mut events = EventStream::from_byte_stream(tokio_stream::iter(chunks));
+    ///
+    /// assert_eq!(events.next().await.unwrap().unwrap().data, "hello");
+    /// # }
+    /// ```
+    pub fn from_byte_stream<S, B, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: Into<Bytes>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self::from_byte_stream_with(stream, EventSourceConfig::default())
+    }
+
+    /// Creates a stream of the events read from a stream of bytes, like
+    /// [`EventStream::from_byte_stream`], with the given [`EventSourceConfig`].
+    ///
+    /// The errors of the stream are yielded as [`EventError::IoError`]s, then the stream ends.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the timeouts of the [`EventSourceConfig`] is set, and the stream is
+    /// created or polled outside of a Tokio runtime with the time driver enabled.
+    pub fn from_byte_stream_with<S, B, E>(stream: S, config: EventSourceConfig) -> Self
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: Into<Bytes>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let state = SharedState::default();
+        let reader = body_reader(Box::pin(stream), &state, &config);
+        let parser = Parser::new(reader, state.clone(), config, Mode::Events);
+        Self::new(Metadata::detached(), state, parser, Parsed::into_event)
+    }
+
+    /// Creates a stream of the events read from a reader, e.g. an SSE capture stored in a file,
+    /// or piped over stdin or a Unix socket, with the default [`EventSourceConfig`].
+    ///
+    /// The events are parsed the same way as the ones of a response, see
+    /// [`EventStream::from_byte_stream`].
+    ///
+    /// ```rust,no_run
+    /// use tokio_stream::StreamExt;
+    ///
+    /// use reqwest_sse::stream::EventStream;
+    ///
+    /// # async fn run() {
+    /// let file = tokio::fs::File::open("capture.sse").await.unwrap();
+    /// let mut events = EventStream::from_reader(file);
+    ///
+    /// while let Some(Ok(event)) = events.next().await {
+    ///     println!("{event:?}");
+    /// }
+    /// # }
+    /// ```
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        Self::from_reader_with(reader, EventSourceConfig::default())
+    }
+
+    /// Creates a stream of the events read from a reader, like [`EventStream::from_reader`],
+    /// with the given [`EventSourceConfig`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the timeouts of the [`EventSourceConfig`] is set, and the stream is
+    /// created or polled outside of a Tokio runtime with the time driver enabled.
+    pub fn from_reader_with<R>(reader: R, config: EventSourceConfig) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        // read as chunks, so the bytes are counted and the timeouts applied as for a response
+        Self::from_byte_stream_with(ReaderStream::new(reader), config)
+    }
+
+    /// Reads the next event, lent until the next call, as a lower-level alternative to
+    /// [`StreamExt::next`] for consumers only inspecting events, e.g. proxies.
+    ///
+    /// The allocations of a lent event are reused by the next one, so consuming a stream this way
+    /// doesn't allocate per event once they're large enough. Events to keep are cloned with
+    /// [`ToOwned::to_owned`].
+    ///
+    /// ```rust,no_run
+    /// # use reqwest_sse::EventSource;
+    /// # async fn run() {
+    /// let mut events = reqwest::get("https://sse.test-free.online/api/story")
+    ///     .await.unwrap()
+    ///     .events()
+    ///     .await.unwrap();
+    ///
+    /// while let Some(Ok(event)) = events.next_ref().await {
+    ///     if &*event.event_type == "story" {
+    ///         println!("{}", event.data);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn next_ref(&mut self) -> Option<Result<&Event, EventError>> {
+        if let Some(event) = self.lent.take()
+            && let Some(parser) = &mut self.parser
+        {
+            parser.recycle(event);
+        }
+        match self.next().await? {
+            Ok(event) => Some(Ok(self.lent.insert(event))),
+            Err(error) => Some(Err(error)),
+        }
+    }
+
+    /// Spawns a task driving the stream, calling the handler with each event in turn.
+    ///
+    /// The task ends with the stream, or as soon as the stream yields an error or the handler
+    /// fails, reporting why with a [`SubscribeError`]. Aborting the returned handle drops the
+    /// stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn subscribe<F, Fut, E>(
+        mut self,
+        mut handler: F,
+    ) -> JoinHandle<Result<(), SubscribeError<E>>>
+    where
+        F: FnMut(Event) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send,
+        E: Send + 'static,
+    {
+        let name = format!("reqwest-sse subscriber {}", self.url());
+        crate::spawn(&name, async move {
+            while let Some(result) = self.next().await {
+                let event = result.map_err(SubscribeError::EventError)?;
+                handler(event).await.map_err(SubscribeError::HandlerError)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<T> std::fmt::Debug for EventStream<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("metadata", &self.metadata)
+            .field("state", &*self.state.lock())
+            .field("terminated", &self.parser.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = Result<T, EventError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while let Some(parser) = &mut this.parser {
+            match std::task::ready!(Pin::new(parser).poll_next(cx)) {
+                Some(Ok(item)) => {
+                    if let Some(item) = (this.convert)(item) {
+                        return Poll::Ready(Some(Ok(item)));
+                    }
+                }
+                Some(Err(error)) => return Poll::Ready(Some(Err(error))),
+                None => this.parser = None,
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+/// Once ended or closed, the stream keeps returning `None` without polling the underlying one.
+impl<T> FusedStream for EventStream<T> {
+    fn is_terminated(&self) -> bool {
+        self.parser.is_none()
+    }
+}
+
+/// Rest of the response body of an [`EventStream`], returned by [`EventStream::into_inner`].
+pub struct Remaining {
+    /// Bytes of the line being read by the parser, consumed from the reader.
+    pending: Vec<u8>,
+    /// Number of pending bytes already read.
+    position: usize,
+    reader: BodyReader,
+    /// Whether the last line read by the parser ended with `\r`, so a leading `\n` is part of
+    /// its terminator.
+    skip_lf: bool,
+}
+
+impl std::fmt::Debug for Remaining {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Remaining").finish_non_exhaustive()
+    }
+}
+
+impl AsyncBufRead for Remaining {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.position < this.pending.len() {
+            return Poll::Ready(Ok(&this.pending[this.position..]));
+        }
+        if this.skip_lf {
+            let available = std::task::ready!(Pin::new(&mut this.reader).poll_fill_buf(cx))?;
+            let skip = available.first() == Some(&b'\n');
+            this.skip_lf = false;
+            if skip {
+                Pin::new(&mut this.reader).consume(1);
+            }
+        }
+        Pin::new(&mut this.reader).poll_fill_buf(cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amount: usize) {
+        let this = self.get_mut();
+        if this.position < this.pending.len() {
+            this.position += amount;
+        } else {
+            Pin::new(&mut this.reader).consume(amount);
+        }
+    }
+}
+
+impl AsyncRead for Remaining {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let available = std::task::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let amount = available.len().min(buf.remaining());
+        buf.put_slice(&available[..amount]);
+        self.consume(amount);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::io::AsyncReadExt;
+    use tokio_util::io::StreamReader;
+
+    use super::*;
+    use crate::EventOrComment;
+
+    fn assert_send_static<T: Send + 'static>() {}
+
+    fn assert_unpin<T: Unpin>() {}
+
+    fn assert_futures_stream<T: futures_core::Stream>() {}
+
+    fn metadata() -> Metadata {
+        Metadata {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            url: Url::parse("http://localhost/sse").unwrap(),
+        }
+    }
+
+    /// Creates a stream of the events read from the chunks, then waiting forever.
+    fn event_stream(chunks: &[&'static [u8]]) -> EventStream {
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok::<_, io::Error>(*chunk))
+            .collect::<Vec<_>>();
+        let reader = StreamReader::new(tokio_stream::iter(chunks).chain(tokio_stream::pending()));
+        let state = SharedState::default();
+        let parser = Parser::new(
+            Box::new(reader),
+            state.clone(),
+            EventSourceConfig::default(),
+            Mode::Events,
+        );
+        EventStream::new(metadata(), state, parser, Parsed::into_event)
+    }
+
+    #[tokio::test]
+    async fn lend_events_reusing_their_allocations() {
+        let mut stream = event_stream(&[b"id: 1\ndata: first\n\n", b"data: second\n\n"]);
+
+        let first = stream.next_ref().await.unwrap().unwrap();
+        assert_eq!(first.data, "first");
+        assert_eq!(first.last_event_id.as_deref(), Some("1"));
+        let data = first.data.as_ptr();
+        let last_event_id = first.last_event_id.as_ref().unwrap().as_ptr();
+
+        let second = stream.next_ref().await.unwrap().unwrap();
+        assert_eq!(second.data, "second");
+        assert_eq!(second.data.as_ptr(), data);
+        assert_eq!(second.last_event_id.as_deref(), Some("1"));
+        assert_eq!(
+            second.last_event_id.as_ref().unwrap().as_ptr(),
+            last_event_id
+        );
+    }
+
+    /// Creates a stream of the events read from the chunks, counting the chunks read.
+    fn counted_event_stream(chunks: &[&'static [u8]]) -> (EventStream, Arc<AtomicUsize>) {
+        let read = Arc::new(AtomicUsize::new(0));
+        let counter = read.clone();
+        let chunks = chunks
+            .iter()
+            .map(|chunk| Ok::<_, io::Error>(*chunk))
+            .collect::<Vec<_>>();
+        let chunks = tokio_stream::iter(chunks).map(move |chunk| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            chunk
+        });
+        let reader = StreamReader::new(chunks.chain(tokio_stream::pending()));
+        let state = SharedState::default();
+        let parser = Parser::new(
+            Box::new(reader),
+            state.clone(),
+            EventSourceConfig::default(),
+            Mode::Events,
+        );
+        let stream = EventStream::new(metadata(), state, parser, Parsed::into_event);
+        (stream, read)
+    }
+
+    #[tokio::test]
+    async fn parse_byte_stream() {
+        let chunks: Vec<Result<&'static [u8], io::Error>> = vec![
+            Ok(b"data: fir"),
+            Ok(b"st\n\ndata: second\n\n"),
+            Err(io::Error::other("connection reset")),
+        ];
+        let mut stream = EventStream::from_byte_stream(tokio_stream::iter(chunks));
+
+        assert_eq!(stream.next().await.unwrap().unwrap().data, "first");
+        assert_eq!(stream.next().await.unwrap().unwrap().data, "second");
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(EventError::IoError(_)))
+        ));
+        assert!(stream.next().await.is_none());
+        assert_eq!(stream.stats().bytes_consumed, 27);
+        assert_eq!(stream.url().as_str(), "about:blank");
+    }
+
+    #[test]
+    fn parse_byte_stream_outside_runtime() {
+        let chunks = [Ok::<_, io::Error>(Bytes::from("data: hello\n\n"))];
+        let mut stream = EventStream::from_byte_stream(tokio_stream::iter(chunks));
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+
+        assert!(matches!(
+            Pin::new(&mut stream).poll_next(&mut cx),
+            Poll::Ready(Some(Ok(event))) if event.data == "hello"
+        ));
+        assert!(matches!(
+            Pin::new(&mut stream).poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+
+    #[tokio::test]
+    async fn end_never_pending_byte_stream_after_max_duration() {
+        /// Stream of chunks always ready, unlike `tokio_stream::iter` yielding to the runtime.
+        struct Repeat;
+
+        impl Stream for Repeat {
+            type Item = io::Result<Bytes>;
+
+            fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                Poll::Ready(Some(Ok(Bytes::from("data: again\n\n"))))
+            }
+        }
+
+        let config = EventSourceConfig::new().max_duration(Duration::from_millis(50));
+        let events = EventStream::from_byte_stream_with(Repeat, config);
+
+        let events =
+            tokio::time::timeout(Duration::from_secs(5), events.fold(0, |count, _| count + 1))
+                .await;
+        assert!(events.unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn parse_reader_as_byte_stream() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/simple_event_stream.sse"
+        );
+        let file = tokio::fs::File::open(path).await.unwrap();
+        let events = EventStream::from_reader(file).collect::<Vec<_>>().await;
+
+        let content = Bytes::from(std::fs::read(path).unwrap());
+        let expected =
+            EventStream::from_byte_stream(tokio_stream::once(Ok::<_, io::Error>(content)))
+                .collect::<Vec<_>>()
+                .await;
+
+        assert!(!events.is_empty());
+        assert_eq!(
+            events.into_iter().map(Result::unwrap).collect::<Vec<_>>(),
+            expected.into_iter().map(Result::unwrap).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn read_lazily() {
+        let (mut stream, read) =
+            counted_event_stream(&[b"data: 1\n\n", b"data: 2\n\n", b"data: 3\n\n"]);
+        assert_eq!(read.load(Ordering::SeqCst), 0);
+
+        assert_eq!(stream.next().await.unwrap().unwrap().data, "1");
+        assert_eq!(read.load(Ordering::SeqCst), 1);
+        tokio::task::yield_now().await;
+        assert_eq!(read.load(Ordering::SeqCst), 1);
+
+        assert_eq!(stream.next().await.unwrap().unwrap().data, "2");
+        assert_eq!(read.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn bound_channel_read_ahead() {
+        let chunks = [&b"data: event\n\n"[..]; 10];
+        let (stream, read) = counted_event_stream(&chunks);
+        let mut receiver = stream.into_channel(2);
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(read.load(Ordering::SeqCst), 3);
+
+        receiver.recv().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(read.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn batch_ready_events() {
+        let mut stream = event_stream(&[b"data: 1\n\ndata: 2\n\n", b"data: 3\n\ndata: 4\n\n"]);
+        let data = |batch: Vec<Result<Event, EventError>>| {
+            batch
+                .into_iter()
+                .map(|event| event.unwrap().data)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(data(stream.next_batch(3).await.unwrap()), ["1", "2", "3"]);
+        assert_eq!(data(stream.next_batch(3).await.unwrap()), ["4"]);
+        let next = tokio::time::timeout(Duration::from_millis(10), stream.next_batch(3)).await;
+        assert!(next.is_err());
+
+        let mut stream = EventStream::new(
+            metadata(),
+            SharedState::default(),
+            Parser::new(
+                Box::new(&b"data: last\n\n"[..]),
+                SharedState::default(),
+                EventSourceConfig::default(),
+                Mode::Events,
+            ),
+            Parsed::into_event,
+        );
+        assert_eq!(data(stream.next_batch(3).await.unwrap()), ["last"]);
+        assert!(stream.next_batch(3).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn yield_to_other_tasks_during_bursts() {
+        let burst = "data: event\n\n".repeat(1000).into_bytes();
+        let mut stream = event_stream(&[burst.leak()]);
+        let other = tokio::spawn(async {});
+
+        let mut events = 0;
+        while !other.is_finished() && events < 1000 {
+            stream.next().await.unwrap().unwrap();
+            events += 1;
+        }
+        assert!(events < 1000);
+    }
+
+    #[tokio::test]
+    async fn stay_terminated_once_ended() {
+        let mut stream = EventStream::new(
+            metadata(),
+            SharedState::default(),
+            Parser::new(
+                Box::new(&b"data: first\n\n"[..]),
+                SharedState::default(),
+                EventSourceConfig::default(),
+                Mode::Events,
+            ),
+            Parsed::into_event,
+        );
+
+        assert!(!stream.is_terminated());
+        assert!(stream.next().await.is_some());
+        assert!(stream.next().await.is_none());
+        assert!(stream.is_terminated());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn end_once_closed() {
+        let mut stream = event_stream(&[b"data: first\n\ndata: second\n\n"]);
+
+        assert!(stream.next().await.is_some());
+        stream.close();
+        assert!(stream.is_terminated());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn give_back_line_being_read() {
+        let mut stream = event_stream(&[b"data: first\n\npart", b"ial"]);
+
+        assert_eq!(stream.next().await.unwrap().unwrap().data, "first");
+        let next = tokio::time::timeout(Duration::from_millis(10), stream.next()).await;
+        assert!(next.is_err());
+
+        let mut remaining = [0; 7];
+        stream
+            .into_inner()
+            .unwrap()
+            .read_exact(&mut remaining)
+            .await
+            .unwrap();
+        assert_eq!(&remaining, b"partial");
+    }
+
+    #[test]
+    fn can_be_moved_across_tasks() {
+        assert_send_static::<EventStream>();
+        assert_send_static::<EventStream<EventOrComment>>();
+    }
+
+    #[test]
+    fn can_be_polled_without_pinning() {
+        assert_unpin::<EventStream>();
+        assert_unpin::<EventStream<EventOrComment>>();
+    }
+
+    #[test]
+    fn implement_futures_stream() {
+        assert_futures_stream::<EventStream>();
+        assert_futures_stream::<EventStream<EventOrComment>>();
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "blocking")]
+
+use httpmock::MockServer;
+use reqwest::StatusCode;
+use reqwest_sse::{blocking::EventSource, config::EventSourceConfig, error::EventSourceError};
+
+#[test]
+fn iterate_over_events() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method("GET").path("/sse");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body("id: 1\ndata: first\n\n: comment\ndata: second\n\n");
+    });
+
+    let mut events = reqwest::blocking::get(server.url("/sse"))
+        .unwrap()
+        .events()
+        .unwrap();
+    assert_eq!(events.next().unwrap().unwrap().data, "first");
+    let second = events.next().unwrap().unwrap();
+    assert_eq!(second.data, "second");
+    assert_eq!(second.last_event_id.as_deref(), Some("1"));
+    assert!(events.next().is_none());
+    assert_eq!(events.last_event_id().as_deref(), Some("1"));
+}
+
+#[test]
+fn reject_bad_status() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method("GET").path("/sse");
+        then.status(404);
+    });
+
+    let error = reqwest::blocking::get(server.url("/sse"))
+        .unwrap()
+        .events()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        EventSourceError::BadStatus(StatusCode::NOT_FOUND, None)
+    ));
+}
+
+#[test]
+fn end_on_no_content() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method("GET").path("/sse");
+        then.status(204);
+    });
+
+    let mut events = reqwest::blocking::get(server.url("/sse"))
+        .unwrap()
+        .events()
+        .unwrap();
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn reject_with_validator() {
+    let server = MockServer::start();
+
+    server.mock(|when, then| {
+        when.method("GET").path("/sse");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .header("x-protocol", "1")
+            .body("data: unsupported\n\n");
+    });
+
+    let config =
+        EventSourceConfig::new().validate(|response| match response.headers().get("x-protocol") {
+            Some(version) if version == "2" => Ok(()),
+            _ => Err("unsupported protocol".to_string()),
+        });
+    let error = reqwest::blocking::get(server.url("/sse"))
+        .unwrap()
+        .events_with(config)
+        .unwrap_err();
+    assert!(
+        matches!(error, EventSourceError::Rejected(reason) if reason == "unsupported protocol")
+    );
+}
@@ -0,0 +1,62 @@
+//! Conformance with the [WHATWG event stream interpretation](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation).
+//!
+//! Cases are described in `data/conformance.json`: each one has a `name`, an `input` stream, and
+//! the `events` it produces, with their `type`, `data`, `id`, and optionally `retry` in
+//! milliseconds.
+use std::time::Duration;
+
+use httpmock::MockServer;
+use serde_json::Value;
+use tokio_stream::StreamExt;
+
+use reqwest_sse::{Event, EventSource};
+
+fn parse_event(event: &Value) -> Event {
+    Event {
+        event_type: event["type"].as_str().unwrap().to_string().into(),
+        data: event["data"].as_str().unwrap().to_string(),
+        last_event_id: event["id"].as_str().map(String::from),
+        retry: event["retry"].as_u64().map(Duration::from_millis),
+        extra: Vec::new(),
+        raw: None,
+        received_at: None,
+        sequence: None,
+    }
+}
+
+#[tokio::test]
+async fn conform_to_specification() {
+    let cases: Value = serde_json::from_str(include_str!("data/conformance.json")).unwrap();
+    let server = MockServer::start_async().await;
+
+    for (index, case) in cases.as_array().unwrap().iter().enumerate() {
+        let name = case["name"].as_str().unwrap();
+        let input = case["input"].as_str().unwrap();
+        let path = format!("/{index}");
+        server
+            .mock_async(|when, then| {
+                when.method("GET").path(path.as_str());
+                then.status(200)
+                    .header("content-type", "text/event-stream")
+                    .body(input);
+            })
+            .await;
+
+        let events = reqwest::get(server.url(&path))
+            .await
+            .unwrap()
+            .events()
+            .await
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        let expected = case["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(parse_event)
+            .collect::<Vec<_>>();
+        assert_eq!(events, expected, "case: {name}");
+    }
+}
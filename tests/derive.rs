@@ -0,0 +1,85 @@
+#![cfg(feature = "derive")]
+
+use httpmock::MockServer;
+use reqwest_sse::{EventSource, SseEvent, error::EventError};
+use serde::Deserialize;
+use tokio_stream::StreamExt;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct User {
+    name: String,
+}
+
+#[derive(Debug, PartialEq, SseEvent)]
+enum Message {
+    #[sse(rename = "user-joined")]
+    Joined(User),
+    Ping,
+}
+
+#[derive(Debug, PartialEq, SseEvent)]
+enum Lenient {
+    Ping,
+    #[sse(other)]
+    Unknown,
+}
+
+async fn events(body: &str) -> reqwest_sse::stream::EventStream {
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(body);
+        })
+        .await;
+
+    reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn map_event_types_to_variants() {
+    let body = concat!(
+        "event: user-joined\ndata: {\"name\":\"alice\"}\n\n",
+        "event: Ping\ndata: ignored\n\n",
+        "event: user-joined\ndata: nope\n\n",
+        "event: typing\ndata: bob\n\n",
+    );
+    let messages = events(body)
+        .await
+        .typed::<Message>()
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(messages.len(), 4);
+    assert_eq!(
+        messages[0].as_ref().unwrap(),
+        &Message::Joined(User {
+            name: "alice".to_string()
+        })
+    );
+    assert_eq!(messages[1].as_ref().unwrap(), &Message::Ping);
+    assert!(matches!(&messages[2], Err(EventError::JsonError(error)) if error.data() == "nope"));
+    assert!(
+        matches!(&messages[3], Err(EventError::UnknownEventType(event_type)) if event_type == "typing")
+    );
+}
+
+#[tokio::test]
+async fn map_unknown_event_types_to_other_variant() {
+    let body = "event: Ping\ndata: x\n\ndata: y\n\n";
+    let messages = events(body)
+        .await
+        .typed::<Lenient>()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(messages, vec![Lenient::Ping, Lenient::Unknown]);
+}
@@ -1,12 +1,36 @@
-use std::{pin::Pin, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 
+use bytes::Bytes;
 use httpmock::MockServer;
 
-use reqwest_sse::{Event, EventSource, error::EventError};
+use reqwest::StatusCode;
+use reqwest_sse::{
+    BytesEvent, Event, EventOrComment, EventSource, EventSourceClient, EventSourceRequest,
+    FieldOrDispatch, SseItem,
+    breaker::{CircuitBreaker, CircuitState},
+    config::{
+        EventSourceConfig, IdleActivity, InvalidUtf8Policy, LongLinePolicy, ParseMode,
+        TruncatedEventPolicy,
+    },
+    error::{EventError, EventSourceError, ParseWarning, SubscribeError},
+    manager::{RestartPolicy, SubscriptionManager},
+    pause::Pausable,
+    reconnect::{LastEventIdStore, MemoryStore, ReconnectingEventSource},
+    stream::EventStream,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::broadcast::error::RecvError,
+};
 use tokio_stream::{Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
 
 async fn assert_events(
-    stream: &mut Pin<Box<impl Stream<Item = Result<Event, EventError>>>>,
+    stream: &mut (impl Stream<Item = Result<Event, EventError>> + Unpin),
     expected_events: &[Event],
 ) {
     for expected in expected_events {
@@ -15,6 +39,52 @@ async fn assert_events(
     }
 }
 
+/// Starts a server sending, on each connection, the connection index as event data, then keeping
+/// the connection open without sending anything.
+async fn start_stalling_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        for index in 0.. {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let _ = socket.read(&mut request).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\ndata: {index}\n\n"
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                tokio::time::sleep(Duration::from_hours(1)).await;
+            });
+        }
+    });
+    format!("http://{address}/sse")
+}
+
+/// Starts a server sending, on each connection, the connection index as event data, then a
+/// comment every 10 milliseconds, but no other event.
+async fn start_pinging_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        for index in 0.. {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut request = [0; 1024];
+                let _ = socket.read(&mut request).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\ndata: {index}\n\n"
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                while socket.write_all(b": ping\n").await.is_ok() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            });
+        }
+    });
+    format!("http://{address}/sse")
+}
+
 #[tokio::test]
 async fn process_simple_event_stream() {
     let server = MockServer::start_async().await;
@@ -41,32 +111,2328 @@ async fn process_simple_event_stream() {
         &mut events,
         &[
             Event {
-                event_type: "message".to_string(),
+                event_type: "message".into(),
                 data: "first event".to_string(),
                 last_event_id: None,
                 retry: None,
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
             },
             Event {
-                event_type: "message".to_string(),
+                event_type: "message".into(),
                 data: "second\nevent\nis\nmultiline".to_string(),
                 last_event_id: None,
                 retry: None,
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
             },
             Event {
-                event_type: "metadata".to_string(),
+                event_type: "metadata".into(),
                 data: "event with custom event type".to_string(),
                 last_event_id: None,
                 retry: None,
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
+            },
+            Event {
+                event_type: "message".into(),
+                data: String::new(),
+                last_event_id: Some("empty-event-with-id-and-retry".to_string()),
+                retry: Some(Duration::from_millis(12345)),
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
             },
             Event {
-                event_type: "message".to_string(),
+                event_type: "message".into(),
                 data: "fourth valid event".to_string(),
                 last_event_id: Some("empty-event-with-id-and-retry".to_string()),
                 retry: Some(Duration::from_millis(12345)),
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
+            },
+        ],
+    )
+    .await;
+
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn reconnect_with_last_event_id() {
+    let server = MockServer::start_async().await;
+
+    let first = server
+        .mock_async(|when, then| {
+            when.method("GET")
+                .path("/sse")
+                .header("last-event-id", "seed");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\nid: 1\n\n");
+        })
+        .await;
+    let second = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse").header("last-event-id", "1");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: second\n\n");
+        })
+        .await;
+
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .last_event_id("seed")
+        .retry(Duration::from_millis(10))
+        .events();
+
+    assert_events(
+        &mut events,
+        &[
+            Event {
+                event_type: "message".into(),
+                data: "first".to_string(),
+                last_event_id: Some("1".to_string()),
+                retry: None,
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
+            },
+            Event {
+                event_type: "message".into(),
+                data: "second".to_string(),
+                last_event_id: Some("1".to_string()),
+                retry: None,
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
             },
         ],
     )
     .await;
 
+    first.assert_async().await;
+    second.assert_async().await;
+}
+
+#[tokio::test]
+async fn stop_reconnecting_on_bad_status() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(404);
+        })
+        .await;
+
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request).events();
+
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::SourceError(EventSourceError::BadStatus(
+            StatusCode::NOT_FOUND,
+            None
+        ))))
+    ));
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn persist_last_event_id_in_store() {
+    let server = MockServer::start_async().await;
+
+    let mock = server
+        .mock_async(|when, then| {
+            when.method("GET")
+                .path("/sse")
+                .header("last-event-id", "stored");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\nid: 1\n\ndata: second\nid: 2\n\ndata: reset\nid:\n\ndata: last\n\n");
+        })
+        .await;
+
+    let store = MemoryStore::default();
+    store.save("stored").await;
+
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .store(store.clone())
+        .events();
+
+    let first = events.next().await.unwrap().unwrap();
+    assert_eq!(first.last_event_id.as_deref(), Some("1"));
+    mock.assert_async().await;
+
+    // the first event is saved once the second one is requested
+    assert_eq!(store.load().await.as_deref(), Some("stored"));
+    let second = events.next().await.unwrap().unwrap();
+    assert_eq!(second.last_event_id.as_deref(), Some("2"));
+    assert_eq!(store.load().await.as_deref(), Some("1"));
+
+    // the ID reset by the server is cleared
+    let reset = events.next().await.unwrap().unwrap();
+    assert_eq!(reset.last_event_id, None);
+    events.next().await.unwrap().unwrap();
+    assert_eq!(store.load().await, None);
+}
+
+/// Store taking some time to save, like a file or a database.
+#[derive(Clone, Default)]
+struct SlowStore(Arc<Mutex<Option<String>>>);
+
+impl LastEventIdStore for SlowStore {
+    async fn load(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    async fn save(&self, last_event_id: &str) {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        *self.0.lock().unwrap() = Some(last_event_id.to_string());
+    }
+
+    async fn clear(&self) {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+#[tokio::test]
+async fn save_last_event_id_before_ending() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("id: 1\ndata: first\n\n");
+        })
+        .await;
+
+    let store = SlowStore::default();
+    let token = CancellationToken::new();
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .store(store.clone())
+        .cancellation_token(token.clone())
+        .events();
+
+    let first = events.next().await.unwrap().unwrap();
+    assert_eq!(first.last_event_id.as_deref(), Some("1"));
+    token.cancel();
+    assert!(events.next().await.is_none());
+    assert_eq!(store.load().await.as_deref(), Some("1"));
+}
+
+#[tokio::test]
+async fn open_circuit_after_repeated_failures() {
+    // bind then release a port, so connections to it are refused
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let breaker = CircuitBreaker::new(2, Duration::from_mins(1));
+    let request = reqwest::Client::new().get(format!("http://{address}/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .retry(Duration::from_millis(10))
+        .circuit_breaker(breaker.clone())
+        .events();
+
+    for _ in 0..2 {
+        assert!(matches!(
+            events.next().await,
+            Some(Err(EventError::RequestError(_)))
+        ));
+    }
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::CircuitOpen(CircuitState::Open)))
+    ));
+    assert_eq!(breaker.state(), CircuitState::Open);
+}
+
+#[tokio::test]
+async fn open_circuit_on_bad_status() {
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(503);
+        })
+        .await;
+
+    let breaker = CircuitBreaker::new(1, Duration::from_mins(1));
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request.try_clone().unwrap())
+        .circuit_breaker(breaker.clone())
+        .events();
+
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::SourceError(EventSourceError::BadStatus(
+            StatusCode::SERVICE_UNAVAILABLE,
+            _
+        ))))
+    ));
+    assert!(events.next().await.is_none());
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    // the open circuit delays the other sources sharing the breaker
+    let mut events = ReconnectingEventSource::new(request)
+        .circuit_breaker(breaker)
+        .events();
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::CircuitOpen(CircuitState::Open)))
+    ));
+}
+
+#[tokio::test]
+async fn adopt_permanent_redirect_location() {
+    let server = MockServer::start_async().await;
+
+    let old = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/old");
+            then.status(301).header("location", "/sse");
+        })
+        .await;
+    let sse = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: event\n\n");
+        })
+        .await;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let mut events = ReconnectingEventSource::new(client.get(server.url("/old")))
+        .retry(Duration::from_millis(10))
+        .follow_redirects(3)
+        .events();
+
+    for _ in 0..2 {
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.data, "event");
+    }
+
+    old.assert_hits_async(1).await;
+    sse.assert_hits_async(2).await;
+}
+
+#[tokio::test]
+async fn follow_temporary_redirect_on_each_connection() {
+    let server = MockServer::start_async().await;
+
+    let temporary = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/temporary");
+            then.status(307).header("location", "/sse");
+        })
+        .await;
+    let sse = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: event\n\n");
+        })
+        .await;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let mut events = ReconnectingEventSource::new(client.get(server.url("/temporary")))
+        .retry(Duration::from_millis(10))
+        .follow_redirects(3)
+        .events();
+
+    for _ in 0..2 {
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.data, "event");
+    }
+
+    temporary.assert_hits_async(2).await;
+    sse.assert_hits_async(2).await;
+}
+
+#[tokio::test]
+async fn switch_post_to_get_on_found_redirect() {
+    let server = MockServer::start_async().await;
+
+    let found = server
+        .mock_async(|when, then| {
+            when.method("POST").path("/found");
+            then.status(302).header("location", "/sse");
+        })
+        .await;
+    let sse = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse").matches(|request| {
+                request
+                    .headers
+                    .iter()
+                    .flatten()
+                    .all(|(name, _)| !name.eq_ignore_ascii_case("content-type"))
+            });
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: event\n\n");
+        })
+        .await;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let request = client
+        .post(server.url("/found"))
+        .header("content-type", "application/json")
+        .body("{}");
+    let mut events = ReconnectingEventSource::new(request)
+        .follow_redirects(3)
+        .events();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "event");
+    found.assert_async().await;
+    sse.assert_async().await;
+}
+
+#[tokio::test]
+async fn merge_subscriptions_events() {
+    let server = MockServer::start_async().await;
+
+    for name in ["first", "second"] {
+        server
+            .mock_async(|when, then| {
+                when.method("GET").path(format!("/{name}"));
+                then.status(200)
+                    .header("content-type", "text/event-stream")
+                    .body(format!("data: {name}\n\n"));
+            })
+            .await;
+    }
+
+    let (manager, mut events) = SubscriptionManager::new(reqwest::Client::new(), 8);
+    let manager = manager.restart_policy(RestartPolicy::Never);
+    let first = manager.subscribe(server.url("/first"));
+    let second = manager.subscribe(server.url("/second"));
+    assert_ne!(first.id(), second.id());
+
+    let mut received = Vec::new();
+    for _ in 0..2 {
+        let (id, event) = events.recv().await.unwrap();
+        received.push((id, event.unwrap().data));
+    }
+    received.sort();
+    assert_eq!(
+        received,
+        [
+            (first.id(), "first".to_string()),
+            (second.id(), "second".to_string())
+        ]
+    );
+
+    first.abort();
+    second.abort();
+}
+
+#[tokio::test]
+async fn restart_failed_subscription() {
+    let server = MockServer::start_async().await;
+
+    let mock = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(500);
+        })
+        .await;
+
+    let (manager, mut events) = SubscriptionManager::new(reqwest::Client::new(), 8);
+    let manager = manager.restart_policy(RestartPolicy::Limited(1, Duration::from_millis(10)));
+    let subscription = manager.subscribe(server.url("/sse"));
+
+    for _ in 0..2 {
+        let (id, result) = events.recv().await.unwrap();
+        assert_eq!(id, subscription.id());
+        assert!(matches!(
+            result,
+            Err(EventError::SourceError(EventSourceError::BadStatus(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                None
+            )))
+        ));
+    }
+
+    subscription.wait().await.unwrap();
+    mock.assert_hits_async(2).await;
+}
+
+#[tokio::test]
+async fn dont_restart_ended_or_rejected_subscription() {
+    let server = MockServer::start_async().await;
+
+    let ended = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/ended");
+            then.status(204);
+        })
+        .await;
+    let rejected = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/rejected");
+            then.status(404);
+        })
+        .await;
+
+    let (manager, mut events) = SubscriptionManager::new(reqwest::Client::new(), 8);
+    let manager = manager.restart_policy(RestartPolicy::Always(Duration::from_millis(10)));
+    let ended_subscription = manager.subscribe(server.url("/ended"));
+    let rejected_subscription = manager.subscribe(server.url("/rejected"));
+
+    ended_subscription.wait().await.unwrap();
+    rejected_subscription.wait().await.unwrap();
+    let (_, result) = events.recv().await.unwrap();
+    assert!(matches!(
+        result,
+        Err(EventError::SourceError(EventSourceError::BadStatus(
+            StatusCode::NOT_FOUND,
+            None
+        )))
+    ));
+    ended.assert_hits_async(1).await;
+    rejected.assert_hits_async(1).await;
+}
+
+#[tokio::test]
+async fn configure_each_subscription() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(500).body("down");
+        })
+        .await;
+
+    let (manager, mut events) = SubscriptionManager::new(reqwest::Client::new(), 8);
+    let manager = manager.restart_policy(RestartPolicy::Never);
+    let request = manager.client().get(server.url("/sse"));
+    let subscription = manager.subscribe_with(request, |source| {
+        source.config(EventSourceConfig::new().error_body(16))
+    });
+
+    let (id, result) = events.recv().await.unwrap();
+    assert_eq!(id, subscription.id());
+    assert!(matches!(
+        result,
+        Err(EventError::SourceError(EventSourceError::BadStatus(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Some(body)
+        ))) if body == "down"
+    ));
+}
+
+#[tokio::test]
+async fn end_stream_on_cancellation() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(include_str!("data/simple_event_stream.sse"));
+        })
+        .await;
+
+    let token = CancellationToken::new();
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_until_cancelled(token.clone())
+        .await
+        .unwrap();
+
+    assert!(events.next().await.is_some());
+    token.cancel();
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn end_reconnecting_stream_on_cancellation() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: event\n\n");
+        })
+        .await;
+
+    let token = CancellationToken::new();
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .retry(Duration::from_hours(1))
+        .cancellation_token(token.clone())
+        .events();
+
+    assert!(events.next().await.is_some());
+
+    // the stream is now waiting for the reconnection time
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        token.cancel();
+    });
     assert!(events.next().await.is_none());
 }
+
+#[tokio::test]
+async fn skip_replayed_events() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse").header("last-event-id", "2");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("id: 1\ndata: first\n\nid: 2\ndata: second\n\nid: 3\ndata: third\n\n");
+        })
+        .await;
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("id: 1\ndata: first\n\nid: 2\ndata: second\n\ndata: no id\n\n");
+        })
+        .await;
+
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .retry(Duration::from_millis(10))
+        .deduplicate(16)
+        .events();
+
+    let mut data = Vec::new();
+    for _ in 0..4 {
+        data.push(events.next().await.unwrap().unwrap().data);
+    }
+    assert_eq!(data, ["first", "second", "no id", "third"]);
+}
+
+#[tokio::test]
+async fn fail_on_stale_connection() {
+    let url = start_stalling_server().await;
+
+    let config = EventSourceConfig::new().stale_timeout(Duration::from_millis(50));
+    let mut events = reqwest::get(url)
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::StaleConnection))
+    ));
+    assert!(events.next().await.is_none());
+}
+
+#[test]
+fn read_events_without_time_driver() {
+    let server = MockServer::start();
+    server.mock(|when, then| {
+        when.method("GET").path("/sse");
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body("data: no timers\n\n");
+    });
+
+    // without a timeout, reading events needs no timer
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let mut events = reqwest::get(server.url("/sse"))
+            .await
+            .unwrap()
+            .events()
+            .await
+            .unwrap();
+
+        assert_eq!(events.next().await.unwrap().unwrap().data, "no timers");
+        assert!(events.next().await.is_none());
+    });
+}
+
+#[tokio::test]
+async fn reconnect_on_stale_connection() {
+    let url = start_stalling_server().await;
+
+    let config = EventSourceConfig::new().stale_timeout(Duration::from_millis(50));
+    let mut events = ReconnectingEventSource::new(reqwest::Client::new().get(url))
+        .retry(Duration::from_millis(10))
+        .config(config)
+        .events();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::StaleConnection))
+    ));
+    assert_eq!(events.next().await.unwrap().unwrap().data, "1");
+}
+
+#[tokio::test]
+async fn fail_on_idle_timeout_between_events() {
+    let url = start_pinging_server().await;
+
+    let config = EventSourceConfig::new()
+        .idle_timeout(Duration::from_millis(50))
+        .idle_activity(IdleActivity::Events);
+    let mut events = ReconnectingEventSource::new(reqwest::Client::new().get(url))
+        .retry(Duration::from_millis(10))
+        .config(config)
+        .events();
+
+    // the comments don't reset the timer of the events
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::IdleTimeout))
+    ));
+    assert_eq!(events.next().await.unwrap().unwrap().data, "1");
+}
+
+#[tokio::test]
+async fn count_comments_as_idle_activity() {
+    let url = start_pinging_server().await;
+
+    let config = EventSourceConfig::new().idle_timeout(Duration::from_millis(50));
+    let mut events = reqwest::get(url)
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    let next = tokio::time::timeout(Duration::from_millis(200), events.next()).await;
+    assert!(next.is_err());
+}
+
+#[tokio::test]
+async fn keep_idle_timeout_running_while_paused() {
+    let config = EventSourceConfig::new().idle_timeout(Duration::from_millis(50));
+
+    // the comments received while paused are read on resume, before the timeout is checked
+    let url = start_pinging_server().await;
+    let events = reqwest::get(url)
+        .await
+        .unwrap()
+        .events_with(config.clone())
+        .await
+        .unwrap();
+    let mut events = Pausable::new(events);
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    events.pause();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    events.resume();
+    let next = tokio::time::timeout(Duration::from_millis(100), events.next()).await;
+    assert!(next.is_err());
+
+    // without any data received while paused, the timeout elapsed
+    let url = start_stalling_server().await;
+    let events = reqwest::get(url)
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    let mut events = Pausable::new(events);
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    events.pause();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    events.resume();
+    let next = tokio::time::timeout(Duration::from_millis(10), events.next()).await;
+    assert!(matches!(next, Ok(Some(Err(EventError::IdleTimeout)))));
+}
+
+#[tokio::test]
+async fn count_bytes_as_idle_activity() {
+    let url = start_pinging_server().await;
+
+    let config = EventSourceConfig::new()
+        .idle_timeout(Duration::from_millis(50))
+        .idle_activity(IdleActivity::Bytes);
+    let mut events = reqwest::get(&url)
+        .await
+        .unwrap()
+        .events_with(config.clone())
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    let next = tokio::time::timeout(Duration::from_millis(200), events.next()).await;
+    assert!(next.is_err());
+
+    let url = start_stalling_server().await;
+    let mut events = reqwest::get(url)
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::IdleTimeout))
+    ));
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn reconnect_on_missed_heartbeat() {
+    let url = start_stalling_server().await;
+
+    // distinct from the idle timeout, not elapsed yet
+    let config = EventSourceConfig::new()
+        .heartbeat(Duration::from_millis(30), Duration::from_millis(20))
+        .idle_timeout(Duration::from_mins(1));
+    let mut events = ReconnectingEventSource::new(reqwest::Client::new().get(url))
+        .retry(Duration::from_millis(10))
+        .config(config)
+        .events();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::HeartbeatMissed))
+    ));
+    assert_eq!(events.next().await.unwrap().unwrap().data, "1");
+}
+
+#[tokio::test]
+async fn accept_comments_as_heartbeats() {
+    let url = start_pinging_server().await;
+
+    let config =
+        EventSourceConfig::new().heartbeat(Duration::from_millis(20), Duration::from_millis(30));
+    let mut events = reqwest::get(url)
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    let next = tokio::time::timeout(Duration::from_millis(200), events.next()).await;
+    assert!(next.is_err());
+}
+
+#[tokio::test]
+async fn classify_timeout_errors() {
+    let url = start_stalling_server().await;
+
+    // the client's timeout applies, as the request isn't sent by the crate
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let mut events = client
+        .get(url)
+        .send()
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    let error = events.next().await.unwrap().unwrap_err();
+    assert!(matches!(error, EventError::IoError(_)));
+    assert!(error.is_timeout());
+
+    assert!(EventError::HeartbeatMissed.is_timeout());
+    assert!(!EventError::LineTooLong(1024).is_timeout());
+}
+
+#[tokio::test]
+async fn fail_on_first_event_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0; 1024];
+        let _ = socket.read(&mut request).await.unwrap();
+        let response = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\n: welcome\n";
+        socket.write_all(response.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_hours(1)).await;
+    });
+
+    let config = EventSourceConfig::new().first_event_timeout(Duration::from_millis(50));
+    let mut events = reqwest::get(format!("http://{address}/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::FirstEventTimeout))
+    ));
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn disarm_first_event_timeout_once_received() {
+    let url = start_stalling_server().await;
+
+    let config = EventSourceConfig::new().first_event_timeout(Duration::from_millis(50));
+    let mut events = reqwest::get(url)
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    let next = tokio::time::timeout(Duration::from_millis(200), events.next()).await;
+    assert!(next.is_err());
+}
+
+#[tokio::test]
+async fn end_after_max_duration_flushing_received_events() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0; 1024];
+        let _ = socket.read(&mut request).await.unwrap();
+        let response = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\n\r\ndata: 0\n\n";
+        socket.write_all(response.as_bytes()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        socket
+            .write_all(b"data: 1\n\ndata: 2\n\ndata: partial")
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_hours(1)).await;
+    });
+
+    let config = EventSourceConfig::new().max_duration(Duration::from_millis(50));
+    let mut events = reqwest::get(format!("http://{address}/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "0");
+    // the following events are received before the end, but read after
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(events.next().await.unwrap().unwrap().data, "1");
+    assert_eq!(events.next().await.unwrap().unwrap().data, "2");
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn stop_reconnecting_after_max_duration() {
+    let url = start_stalling_server().await;
+
+    let config = EventSourceConfig::new()
+        .stale_timeout(Duration::from_millis(20))
+        .max_duration(Duration::from_millis(100));
+    let events = ReconnectingEventSource::new(reqwest::Client::new().get(url))
+        .retry(Duration::from_millis(10))
+        .config(config)
+        .events()
+        .collect::<Vec<_>>()
+        .await;
+
+    // the stream ends, after reconnecting on the stale connections
+    assert_eq!(events[0].as_ref().unwrap().data, "0");
+    assert!(matches!(events[1], Err(EventError::StaleConnection)));
+}
+
+#[tokio::test]
+async fn process_all_line_terminators() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: crlf\r\n\r\ndata: cr\r\rdata: mixed\r\ndata: lines\n\r\n");
+        })
+        .await;
+
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap()
+        .map(|event| event.unwrap().data)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(events, ["crlf", "cr", "mixed\nlines"]);
+}
+
+#[tokio::test]
+async fn ignore_leading_bom() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("\u{FEFF}data: first\n\n");
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+}
+
+#[tokio::test]
+async fn parse_sloppy_stream_leniently() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("Event: update\nData: first\nretry: 100ms\n\ndata: truncated");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().parse_mode(ParseMode::Lenient);
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_events(
+        &mut events,
+        &[
+            Event {
+                event_type: "update".into(),
+                data: "first".to_string(),
+                last_event_id: None,
+                retry: Some(Duration::from_millis(100)),
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
+            },
+            Event {
+                event_type: "message".into(),
+                data: "truncated".to_string(),
+                last_event_id: None,
+                retry: Some(Duration::from_millis(100)),
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
+            },
+        ],
+    )
+    .await;
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn yield_comments_on_demand() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(": keep-alive\n\ndata: event\n:raw\n\n");
+        })
+        .await;
+
+    let items = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with_comments(EventSourceConfig::new())
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(
+        items,
+        [
+            EventOrComment::Comment("keep-alive".to_string()),
+            EventOrComment::Comment("raw".to_string()),
+            EventOrComment::Event(Event {
+                event_type: "message".into(),
+                data: "event".to_string(),
+                last_event_id: None,
+                retry: None,
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
+            }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn report_truncated_event() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: complete\n\ndata: truncated\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().truncated_event(TruncatedEventPolicy::Error);
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "complete");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::TruncatedEvent(event))) if event.data == "truncated"
+    ));
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn report_invalid_utf8() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(b"data: caf\xC3\xA9\n\ndata: \xFF\n\n".as_slice());
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "café");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::Utf8Error(_)))
+    ));
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn apply_invalid_utf8_policy() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(b"data: first\ndata: \xFF\n\ndata: second\n\n".as_slice());
+        })
+        .await;
+
+    let config = EventSourceConfig::new().invalid_utf8(InvalidUtf8Policy::Lossy);
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    let data = events
+        .map(|event| event.unwrap().data)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(data, ["first\n\u{FFFD}", "second"]);
+
+    let config = EventSourceConfig::new().invalid_utf8(InvalidUtf8Policy::SkipEvent);
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    let data = events
+        .map(|event| event.unwrap().data)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(data, ["second"]);
+}
+
+#[tokio::test]
+async fn limit_line_length() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: short\n\ndata: far too long\n\ndata: again\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new()
+        .max_line_length(12)
+        .long_line(LongLinePolicy::Skip);
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "short");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::LineTooLong(12)))
+    ));
+    assert_eq!(events.next().await.unwrap().unwrap().data, "again");
+    assert!(events.next().await.is_none());
+
+    let config = EventSourceConfig::new().max_line_length(12);
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "short");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::LineTooLong(12)))
+    ));
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn limit_event_size() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: 1234\ndata: 5\n\ndata: 1234\ndata: 56\ndata: 7\n\ndata: next\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().max_event_size(6);
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await.unwrap().unwrap().data, "1234\n5");
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::EventTooLarge(6)))
+    ));
+    assert_eq!(events.next().await.unwrap().unwrap().data, "next");
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn read_through_small_buffer() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("id: 1\r\ndata: first\r\n\r\ndata: second\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().read_buffer_capacity(3);
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap()
+        .map(|event| event.unwrap().data)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(events, ["first", "second"]);
+}
+
+#[tokio::test]
+async fn report_stream_stats() {
+    let server = MockServer::start_async().await;
+
+    let body = "data: first\n\n: comment\n\ndata: second\n\n";
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(body);
+        })
+        .await;
+
+    let before = SystemTime::now();
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap();
+    let stats = events.stats();
+    assert_eq!(stats.events_received, 0);
+    assert!(stats.connected_at.unwrap() >= before);
+    assert_eq!(stats.last_event_at, None);
+
+    while events.next().await.is_some() {}
+    let stats = events.stats();
+    assert_eq!(stats.events_received, 2);
+    assert_eq!(stats.bytes_consumed, body.len() as u64);
+    assert!(stats.last_event_at.unwrap() >= stats.connected_at.unwrap());
+    assert_eq!(stats.reconnects, 0);
+    let event_types = events.event_type_stats();
+    assert_eq!(event_types.len(), 1);
+    assert_eq!(event_types["message"].events_received, 2);
+    assert_eq!(
+        Some(event_types["message"].last_event_at),
+        stats.last_event_at
+    );
+
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .retry(Duration::from_millis(10))
+        .events();
+    for _ in 0..3 {
+        events.next().await.unwrap().unwrap();
+    }
+    let stats = events.stats();
+    assert_eq!(stats.events_received, 3);
+    assert_eq!(stats.reconnects, 1);
+}
+
+#[tokio::test]
+async fn filter_event_types_in_parser() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(concat!(
+                    "event: a\ndata: 1\n\n",
+                    "data: 2\nevent: b\n\n",
+                    "data: message\n\n",
+                    "event: c\nid: 7\ndata: filtered\n\n",
+                    "event: b\ndata: 3\n\n",
+                ));
+        })
+        .await;
+
+    let config = EventSourceConfig::new().event_types(["a", "b"]);
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap()
+        .map(|event| {
+            let event = event.unwrap();
+            (
+                event.event_type.to_string(),
+                event.data,
+                event.last_event_id,
+            )
+        })
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(
+        events,
+        [
+            ("a".to_string(), "1".to_string(), None),
+            ("b".to_string(), "2".to_string(), None),
+            ("b".to_string(), "3".to_string(), Some("7".to_string())),
+        ]
+    );
+
+    // a later `event` line accepts the event again
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/message");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("event: foo\nevent: message\ndata: x\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().event_types(["message"]);
+    let mut events = reqwest::get(server.url("/message"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    let event = events.next().await.unwrap().unwrap();
+    assert_eq!((&*event.event_type, &*event.data), ("message", "x"));
+    assert!(events.next().await.is_none());
+}
+
+#[tokio::test]
+async fn collect_unknown_fields() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("seq: 1\ndata: first\ntime: noon\n\ndata: second\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().unknown_fields(true);
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    let first = events.next().await.unwrap().unwrap();
+    assert_eq!(
+        first.extra,
+        [
+            ("seq".to_string(), "1".to_string()),
+            ("time".to_string(), "noon".to_string())
+        ]
+    );
+    let second = events.next().await.unwrap().unwrap();
+    assert!(second.extra.is_empty());
+}
+
+#[tokio::test]
+async fn capture_raw_events() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("event: update\r\n: note\r\ndata:first\r\n\r\n\r\ndata: second\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().raw(true);
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    let raw = events
+        .map(|event| event.unwrap().raw)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        raw,
+        [
+            Some("event: update\n: note\ndata:first\n".to_string()),
+            Some("data: second\n".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn report_parse_warnings() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("seq: 1\nretry: soon\nid: a\0b\ndata: first\n\n");
+        })
+        .await;
+
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let config = EventSourceConfig::new().on_warning({
+        let warnings = warnings.clone();
+        move |warning| warnings.lock().unwrap().push(warning)
+    });
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+
+    assert_eq!(events.collect::<Vec<_>>().await.len(), 1);
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        [
+            ParseWarning::UnknownField("seq".to_string()),
+            ParseWarning::InvalidRetry("soon".to_string()),
+            ParseWarning::NullInId("a\0b".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn accept_content_type_with_parameters() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream; charset=utf-8")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+}
+
+#[tokio::test]
+async fn accept_configured_status() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(201)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let response = reqwest::get(server.url("/sse")).await.unwrap();
+    assert!(matches!(
+        response.events().await,
+        Err(EventSourceError::BadStatus(StatusCode::CREATED, None))
+    ));
+
+    let config = EventSourceConfig::new().accept_any_success();
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events_with(config)
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+}
+
+#[tokio::test]
+async fn reject_response_with_validator() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .header("x-protocol-version", "1")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().validate(|response| {
+        match response.headers().get("x-protocol-version") {
+            Some(version) if version == "2" => Ok(()),
+            _ => Err("unsupported protocol version".to_string()),
+        }
+    });
+    let response = reqwest::get(server.url("/sse")).await.unwrap();
+    assert!(matches!(
+        response.events_with(config).await,
+        Err(EventSourceError::Rejected(reason)) if reason == "unsupported protocol version"
+    ));
+}
+
+#[tokio::test]
+async fn end_on_no_content() {
+    let server = MockServer::start_async().await;
+
+    let mock = server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(204);
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .unwrap()
+        .events()
+        .await
+        .unwrap();
+    assert!(events.next().await.is_none());
+
+    let request = reqwest::Client::new().get(server.url("/sse"));
+    let mut events = ReconnectingEventSource::new(request)
+        .retry(Duration::from_millis(10))
+        .events();
+    assert!(events.next().await.is_none());
+    mock.assert_hits_async(2).await;
+}
+
+#[tokio::test]
+async fn convert_response_result() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+
+    let result = reqwest::get("http://invalid host/sse").await.events().await;
+    assert!(matches!(result, Err(EventSourceError::RequestError(_))));
+}
+
+#[tokio::test]
+async fn send_request_and_convert_response() {
+    let server = MockServer::start_async().await;
+
+    let mock = server
+        .mock_async(|when, then| {
+            when.method("GET")
+                .path("/sse")
+                .header("accept", "text/event-stream");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let client = reqwest::Client::new();
+    let mut events = client.get(server.url("/sse")).events().await.unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+
+    let mut events = client.get(server.url("/sse")).reconnecting().events();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+    mock.assert_hits_async(2).await;
+}
+
+#[tokio::test]
+async fn outlive_client_timeout() {
+    let server = MockServer::start_async().await;
+
+    let mock = server
+        .mock_async(|when, then| {
+            when.method("GET")
+                .path("/sse")
+                .header("accept", "text/event-stream")
+                .header("cache-control", "no-cache")
+                .header("accept-encoding", "identity");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\n")
+                .delay(Duration::from_millis(200));
+        })
+        .await;
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let mut events = client.sse(server.url("/sse")).events().await.unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn expose_response_metadata() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .header("x-request-id", "42")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    assert_eq!(events.status(), StatusCode::OK);
+    assert_eq!(events.headers()["x-request-id"], "42");
+    assert_eq!(events.url().as_str(), server.url("/sse"));
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+}
+
+#[tokio::test]
+async fn accept_configured_content_type() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "application/vnd.acme.event-stream; v=2")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let response = reqwest::get(server.url("/sse")).await.unwrap();
+    assert!(matches!(
+        response.events().await,
+        Err(EventSourceError::BadContentType(Some(_)))
+    ));
+
+    let config =
+        EventSourceConfig::new().accept_content_type(|essence| essence.ends_with("event-stream"));
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .events_with(config)
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+}
+
+#[tokio::test]
+async fn accept_any_content_type() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/plain")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().danger_accept_any_content_type();
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .events_with(config)
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "first");
+}
+
+#[tokio::test]
+async fn capture_error_body() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(401).body("{\"error\": \"invalid token\"}");
+        })
+        .await;
+
+    let config = EventSourceConfig::new().error_body(16);
+    let result = reqwest::get(server.url("/sse"))
+        .await
+        .events_with(config)
+        .await;
+    assert!(matches!(
+        result,
+        Err(EventSourceError::BadStatus(StatusCode::UNAUTHORIZED, Some(body)))
+            if body == "{\"error\": \"inval"
+    ));
+}
+
+#[tokio::test]
+async fn time_out_connection() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\n")
+                .delay(Duration::from_millis(500));
+        })
+        .await;
+
+    let config = EventSourceConfig::new().connect_timeout(Duration::from_millis(50));
+    let client = reqwest::Client::new();
+    let result = client
+        .get(server.url("/sse"))
+        .events_with(config.clone())
+        .await;
+    assert!(matches!(result, Err(EventSourceError::ConnectTimeout)));
+
+    let mut events = ReconnectingEventSource::new(client.get(server.url("/sse")))
+        .config(config)
+        .events();
+    assert!(matches!(
+        events.next().await,
+        Some(Err(EventError::ConnectTimeout))
+    ));
+}
+
+/// Consumer storing the stream in a field, which requires naming its type.
+struct Consumer {
+    events: EventStream,
+}
+
+impl Consumer {
+    async fn next_data(&mut self) -> Option<String> {
+        Some(self.events.next().await?.unwrap().data)
+    }
+}
+
+#[tokio::test]
+async fn store_stream_in_struct() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    let mut consumer = Consumer { events };
+    assert_eq!(consumer.next_data().await.as_deref(), Some("first"));
+    assert_eq!(consumer.next_data().await, None);
+}
+
+#[tokio::test]
+async fn consume_stream_in_spawned_task() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\ndata: second\n\n");
+        })
+        .await;
+
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    let task = tokio::spawn(async move {
+        events
+            .map(|event| event.unwrap().data)
+            .collect::<Vec<_>>()
+            .await
+    });
+    assert_eq!(task.await.unwrap(), ["first", "second"]);
+}
+
+#[tokio::test]
+async fn track_last_event_id() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("id: 1\ndata: first\n\ndata: second\n\nid: 2\n\ndata: third\n\nid\n\n");
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    assert_eq!(events.last_event_id(), None);
+    events.next().await.unwrap().unwrap();
+    assert_eq!(events.last_event_id().as_deref(), Some("1"));
+    events.next().await.unwrap().unwrap();
+    assert_eq!(events.last_event_id().as_deref(), Some("1"));
+    // the block without data still updates the last event ID
+    events.next().await.unwrap().unwrap();
+    assert_eq!(events.last_event_id().as_deref(), Some("2"));
+    assert!(events.next().await.is_none());
+    assert_eq!(events.last_event_id(), None);
+}
+
+#[tokio::test]
+async fn track_retry() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\nretry: 1000\n\ndata: second\n\nretry: 2000\n");
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    events.next().await.unwrap().unwrap();
+    assert_eq!(events.retry(), None);
+    // the block without data still updates the reconnection time
+    events.next().await.unwrap().unwrap();
+    assert_eq!(events.retry(), Some(Duration::from_secs(1)));
+    assert!(events.next().await.is_none());
+    assert_eq!(events.retry(), Some(Duration::from_secs(2)));
+}
+
+#[tokio::test]
+async fn recover_remaining_body() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: upgrade\r\n\r\n\x00\x01raw bytes");
+        })
+        .await;
+
+    let mut events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    assert_eq!(events.next().await.unwrap().unwrap().data, "upgrade");
+
+    let mut body = Vec::new();
+    events
+        .into_inner()
+        .unwrap()
+        .read_to_end(&mut body)
+        .await
+        .unwrap();
+    assert_eq!(body, b"\x00\x01raw bytes");
+}
+
+#[tokio::test]
+async fn subscribe_until_handler_fails() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\ndata: stop\n\ndata: third\n\n");
+        })
+        .await;
+
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let task = events.subscribe({
+        let received = received.clone();
+        move |event| {
+            let received = received.clone();
+            async move {
+                if event.data == "stop" {
+                    return Err("stopped");
+                }
+                received.lock().unwrap().push(event.data);
+                Ok(())
+            }
+        }
+    });
+
+    let result = task.await.unwrap();
+    assert!(matches!(
+        result,
+        Err(SubscribeError::HandlerError("stopped"))
+    ));
+    assert_eq!(*received.lock().unwrap(), ["first"]);
+}
+
+#[tokio::test]
+async fn subscribe_until_end_of_stream() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\ndata: second\n\n");
+        })
+        .await;
+
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let task = events.subscribe({
+        let received = received.clone();
+        move |event| {
+            received.lock().unwrap().push(event.data);
+            async { Ok::<_, ()>(()) }
+        }
+    });
+
+    assert!(task.await.unwrap().is_ok());
+    assert_eq!(*received.lock().unwrap(), ["first", "second"]);
+}
+
+#[tokio::test]
+async fn receive_events_from_channel() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\ndata: second\n\n");
+        })
+        .await;
+
+    let mut receiver = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap()
+        .into_channel(1);
+    assert_eq!(receiver.recv().await.unwrap().unwrap().data, "first");
+    assert_eq!(receiver.recv().await.unwrap().unwrap().data, "second");
+    assert!(receiver.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn broadcast_events_to_receivers() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\ndata: second\n\ndata: third\n\n");
+        })
+        .await;
+
+    let broadcast = reqwest::get(server.url("/sse"))
+        .await
+        .events()
+        .await
+        .unwrap()
+        .broadcast(2);
+    let mut first = broadcast.subscribe();
+    let mut second = broadcast.subscribe();
+    broadcast.start().await.unwrap();
+
+    // the capacity is exceeded, as nothing has been received yet
+    assert!(matches!(first.recv().await, Err(RecvError::Lagged(1))));
+    assert_eq!(first.recv().await.unwrap().unwrap().data, "second");
+    assert_eq!(first.recv().await.unwrap().unwrap().data, "third");
+    assert!(matches!(first.recv().await, Err(RecvError::Closed)));
+
+    let mut received = Vec::new();
+    loop {
+        match second.recv().await {
+            Ok(event) => received.push(event.unwrap().data),
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+    assert_eq!(received, ["second", "third"]);
+}
+
+#[tokio::test]
+async fn stream_raw_lines() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("\u{FEFF}: hello\r\nevent: ping\rdata:1\n\ncustom\n");
+        })
+        .await;
+
+    let lines = reqwest::get(server.url("/sse"))
+        .await
+        .sse_lines(EventSourceConfig::default())
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(lines, [": hello", "event: ping", "data:1", "", "custom"]);
+}
+
+#[tokio::test]
+async fn stream_fields_and_dispatches() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(": comment\nx-custom: value\ndata:1\n\nid\n");
+        })
+        .await;
+
+    let field = |name: &str, value: &str| FieldOrDispatch::Field {
+        name: name.to_string(),
+        value: value.to_string(),
+    };
+    let fields = reqwest::get(server.url("/sse"))
+        .await
+        .fields(EventSourceConfig::default())
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        fields,
+        [
+            field("x-custom", "value"),
+            field("data", "1"),
+            FieldOrDispatch::Dispatch,
+            field("id", ""),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stream_protocol_items() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(": hello\nretry: 1000\n\ndata: first\n\n");
+        })
+        .await;
+
+    let items = reqwest::get(server.url("/sse"))
+        .await
+        .sse_items(EventSourceConfig::default())
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        items,
+        [
+            SseItem::Comment("hello".to_string()),
+            SseItem::Retry(Duration::from_secs(1)),
+            SseItem::Event(Event {
+                event_type: "message".into(),
+                data: "first".to_string(),
+                last_event_id: None,
+                retry: Some(Duration::from_secs(1)),
+                extra: Vec::new(),
+                raw: None,
+                received_at: None,
+                sequence: None,
+            }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stream_opaque_data_as_bytes() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(&b"id: 1\nevent: blob\ndata: \xFF\xFE\ndata:\x00\n\n: hi\ndata: last"[..]);
+        })
+        .await;
+
+    let config = EventSourceConfig::new().truncated_event(TruncatedEventPolicy::Dispatch);
+    let events = reqwest::get(server.url("/sse"))
+        .await
+        .bytes_events(config)
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        events,
+        [
+            BytesEvent {
+                event_type: "blob".into(),
+                data: Bytes::from_static(b"\xFF\xFE\n\x00"),
+                last_event_id: Some("1".to_string()),
+                retry: None,
+            },
+            BytesEvent {
+                event_type: "message".into(),
+                data: Bytes::from_static(b"last"),
+                last_event_id: Some("1".to_string()),
+                retry: None,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stamp_events_with_reception_time() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\n");
+        })
+        .await;
+
+    let before = SystemTime::now();
+    let event = reqwest::get(server.url("/sse"))
+        .await
+        .events_with(EventSourceConfig::new().received_at(true))
+        .await
+        .unwrap()
+        .next()
+        .await
+        .unwrap()
+        .unwrap();
+    let received_at = event.received_at.unwrap();
+    assert!(before <= received_at && received_at <= SystemTime::now());
+}
+
+#[tokio::test]
+async fn number_events_without_gaps() {
+    let server = MockServer::start_async().await;
+
+    server
+        .mock_async(|when, then| {
+            when.method("GET").path("/sse");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body("data: first\n\ndata: too large\n\ndata: third\n\n");
+        })
+        .await;
+
+    let sequences = reqwest::get(server.url("/sse"))
+        .await
+        .events_with(EventSourceConfig::new().sequence(true).max_event_size(5))
+        .await
+        .unwrap()
+        .filter_map(Result::ok)
+        .map(|event| (event.data, event.sequence))
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        sequences,
+        [
+            ("first".to_string(), Some(0)),
+            ("third".to_string(), Some(1))
+        ]
+    );
+}
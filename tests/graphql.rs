@@ -0,0 +1,78 @@
+#![cfg(feature = "graphql")]
+
+use httpmock::MockServer;
+use reqwest::StatusCode;
+use reqwest_sse::{
+    EventSourceRequest,
+    error::EventSourceError,
+    graphql::{GraphQlEvents, GraphQlMessage, TOKEN_HEADER, reserve},
+};
+use serde_json::Value;
+use tokio_stream::StreamExt;
+
+#[tokio::test]
+async fn stream_operations_on_reserved_connection() {
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method("PUT").path("/graphql/stream");
+            then.status(201).body("token");
+        })
+        .await;
+    server
+        .mock_async(|when, then| {
+            when.method("GET")
+                .path("/graphql/stream")
+                .header(TOKEN_HEADER, "token");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(concat!(
+                    "event: next\ndata: {\"id\":\"1\",\"payload\":{\"data\":{\"n\":1}}}\n\n",
+                    "event: complete\ndata: {\"id\":\"1\"}\n\n",
+                ));
+        })
+        .await;
+
+    let client = reqwest::Client::new();
+    let token = reserve(&client, server.url("/graphql/stream"))
+        .await
+        .unwrap();
+    let events = client
+        .get(server.url("/graphql/stream"))
+        .header(TOKEN_HEADER, token)
+        .events()
+        .await
+        .unwrap();
+    let messages = GraphQlEvents::<_, Value>::single_connection(events)
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await;
+
+    assert!(matches!(
+        &messages[..],
+        [
+            GraphQlMessage::Next { id: Some(next), result },
+            GraphQlMessage::Complete { id: Some(complete) },
+        ] if next == "1" && complete == "1" && result.data.as_ref().unwrap()["n"] == 1
+    ));
+}
+
+#[tokio::test]
+async fn report_rejected_reservation() {
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method("PUT").path("/graphql/stream");
+            then.status(409).body("stream already registered");
+        })
+        .await;
+
+    let error = reserve(&reqwest::Client::new(), server.url("/graphql/stream"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        EventSourceError::BadStatus(StatusCode::CONFLICT, Some(body)) if body == "stream already registered"
+    ));
+}